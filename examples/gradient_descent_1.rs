@@ -1,6 +1,10 @@
 use plotpy::{generate3d, Contour, Curve, Plot};
 
-use optimize_examples::{bracketing, test_math_funcs};
+use optimize_examples::{
+    bracketing::LineMinimizer,
+    optimizer::{Beta, ConjugateGradient, Convergence, GradientDescent, Optimizer, StepSize},
+    test_math_funcs,
+};
 
 use glam::DVec2;
 
@@ -16,14 +20,14 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     let a = 1.;
     let b = 5.;
     let rosenbrock_a = |x: &DVec2| -> f64 { test_math_funcs::rosenbrock(&x.to_array(), a, b) };
-    let rosenbrock_grad_a =
-        |x: &DVec2| -> DVec2 { test_math_funcs::rosenbrock_grad(&x.to_array(), a, b).into() };
 
     let n = 81;
     let (x, y, z) = generate3d(-3.0, 3.0, -3.0, 3.0, n, n, |x, y| {
         rosenbrock_a(&DVec2::new(x, y))
     });
 
+    let objective = test_math_funcs::rosenbrock_objective(a, b);
+
     // configure contour
     let mut contour = Contour::new();
     contour
@@ -41,22 +45,17 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     let mut curve1 = Curve::new();
     {
-        let iter_count = 70;
-        let mut start_point = DVec2::new(-2., -1.);
-        let mut iter_points = vec![];
-
-        for _ in 0..iter_count {
-            iter_points.push(start_point);
-
-            let d = -rosenbrock_grad_a(&start_point).normalize();
-            let f = |alpha| rosenbrock_a(&(start_point + alpha * d));
-
-            let bracket = bracketing::bracket_minimum(&f, 0., 1e-8, 2., 100).unwrap();
-            let bracket = bracketing::golden_section_search(&f, bracket, 30);
-            let learning_rate = (bracket.0 + bracket.1) / 2.;
-
-            start_point += learning_rate * d;
-        }
+        let res = GradientDescent::new(StepSize::Exact(LineMinimizer::new().with_step(1e-8)))
+            .with_convergence(Convergence {
+                max_iter: 70,
+                ..Default::default()
+            })
+            .minimize(&objective, &[-2., -1.])?;
+        let iter_points = res
+            .trajectory
+            .iter()
+            .map(|x| DVec2::from_slice(x))
+            .collect::<Vec<_>>();
 
         curve1.set_line_color("red");
         curve1.set_line_width(0.5);
@@ -66,28 +65,18 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     let mut curve2 = Curve::new();
     {
-        let iter_count = 10;
-        let mut start_point = DVec2::new(-2.1, -1.1);
-        let mut iter_points = vec![start_point];
-
-        let mut g = rosenbrock_grad_a(&start_point);
-        let mut d = -g;
-
-        for _ in 0..iter_count {
-            let f = |alpha| rosenbrock_a(&(start_point + alpha * d));
-
-            let bracket = bracketing::bracket_minimum(&f, 0., 1e-8, 2., 100).unwrap();
-            let bracket = bracketing::golden_section_search(&f, bracket, 30);
-            let learning_rate = (bracket.0 + bracket.1) / 2.;
-
-            start_point += learning_rate * d;
-            iter_points.push(start_point);
-
-            let g_new = rosenbrock_grad_a(&start_point);
-            let beta = ((g_new - g).dot(g_new) / g.length_squared()).max(0.);
-            d = -g_new + beta * d;
-            g = g_new;
-        }
+        let res = ConjugateGradient::new(Beta::PolakRibierePlus)
+            .with_line_step(LineMinimizer::new().with_step(1e-8))
+            .with_convergence(Convergence {
+                max_iter: 10,
+                ..Default::default()
+            })
+            .minimize(&objective, &[-2.1, -1.1])?;
+        let iter_points = res
+            .trajectory
+            .iter()
+            .map(|x| DVec2::from_slice(x))
+            .collect::<Vec<_>>();
 
         curve2.set_line_color("pink");
         curve2.set_line_width(0.5);
@@ -1,6 +1,12 @@
 use plotpy::{generate3d, Contour, Curve, Plot};
 
-use optimize_examples::{bracketing, test_math_funcs};
+use optimize_examples::{
+    bracketing::{LineMinimizer, Tolerance},
+    optimizer::{
+        Beta, Bfgs, ConjugateGradient, Convergence, GradientDescent, Momentum, Optimizer, StepSize,
+    },
+    test_math_funcs,
+};
 
 use glam::DVec2;
 
@@ -34,31 +40,30 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     let a = 1.;
     let b = 100.;
     let rosenbrock_a = |x: &DVec2| -> f64 { test_math_funcs::rosenbrock(&x.to_array(), a, b) };
-    let rosenbrock_grad_a =
-        |x: &DVec2| -> DVec2 { test_math_funcs::rosenbrock_grad(&x.to_array(), a, b).into() };
+
+    let objective = test_math_funcs::rosenbrock_objective(a, b);
 
     let mut contour = Contour::new();
     draw_contour(&mut contour, rosenbrock_a);
 
     let iter_count = 30;
+    let minimizer = LineMinimizer::new()
+        .with_step(1e-8)
+        .with_tolerance(Tolerance::Relative(1e-6));
 
     let mut curve1 = Curve::new();
     {
-        let mut start_point = DVec2::new(-1.4, 0.9);
-        let mut iter_points = vec![];
-
-        for _ in 0..iter_count {
-            iter_points.push(start_point);
-
-            let d = -rosenbrock_grad_a(&start_point).normalize();
-            let f = |alpha| rosenbrock_a(&(start_point + alpha * d));
-
-            let bracket = bracketing::bracket_minimum(&f, 0., 1e-8, 2., 100).unwrap();
-            let bracket = bracketing::golden_section_search(&f, bracket, 3);
-            let learning_rate = (bracket.0 + bracket.1) / 2.;
-
-            start_point += learning_rate * d;
-        }
+        let res = GradientDescent::new(StepSize::Exact(minimizer))
+            .with_convergence(Convergence {
+                max_iter: iter_count,
+                ..Default::default()
+            })
+            .minimize(&objective, &[-1.4, 0.9])?;
+        let iter_points = res
+            .trajectory
+            .iter()
+            .map(|x| DVec2::from_slice(x))
+            .collect::<Vec<_>>();
 
         curve1.set_line_color("red");
         curve1.set_line_width(0.5);
@@ -68,27 +73,18 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     let mut curve2 = Curve::new();
     {
-        let mut start_point = DVec2::new(-1.4, 1.);
-        let mut iter_points = vec![start_point];
-
-        let mut g = rosenbrock_grad_a(&start_point);
-        let mut d = -g;
-
-        for _ in 0..iter_count {
-            let f = |alpha| rosenbrock_a(&(start_point + alpha * d));
-
-            let bracket = bracketing::bracket_minimum(&f, 0., 1e-8, 2., 100).unwrap();
-            let bracket = bracketing::golden_section_search(&f, bracket, 3);
-            let learning_rate = (bracket.0 + bracket.1) / 2.;
-
-            start_point += learning_rate * d;
-            iter_points.push(start_point);
-
-            let g_new = rosenbrock_grad_a(&start_point);
-            let beta = ((g_new - g).dot(g_new) / g.length_squared()).max(0.);
-            d = -g_new + beta * d;
-            g = g_new;
-        }
+        let res = ConjugateGradient::new(Beta::PolakRibierePlus)
+            .with_line_step(minimizer)
+            .with_convergence(Convergence {
+                max_iter: iter_count,
+                ..Default::default()
+            })
+            .minimize(&objective, &[-1.4, 1.])?;
+        let iter_points = res
+            .trajectory
+            .iter()
+            .map(|x| DVec2::from_slice(x))
+            .collect::<Vec<_>>();
 
         curve2.set_line_color("white");
         curve2.set_line_width(0.5);
@@ -98,24 +94,17 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     let mut curve3 = Curve::new();
     {
-        let mut start_point = DVec2::new(-1.4, 1.1);
-        let mut iter_points = vec![];
-        let mut v = DVec2::ZERO;
-
-        for _ in 0..iter_count {
-            iter_points.push(start_point);
-
-            let d = -rosenbrock_grad_a(&start_point).normalize();
-            let f = |alpha| rosenbrock_a(&(start_point + alpha * d));
-
-            let bracket = bracketing::bracket_minimum(&f, 0., 1e-8, 2., 100).unwrap();
-            let bracket = bracketing::golden_section_search(&f, bracket, 3);
-            let learning_rate = (bracket.0 + bracket.1) / 2.;
-
-            v = 0.7 * v + learning_rate * d;
-
-            start_point += v;
-        }
+        let res = Momentum::new(StepSize::Exact(minimizer), 0.7)
+            .with_convergence(Convergence {
+                max_iter: iter_count,
+                ..Default::default()
+            })
+            .minimize(&objective, &[-1.4, 1.1])?;
+        let iter_points = res
+            .trajectory
+            .iter()
+            .map(|x| DVec2::from_slice(x))
+            .collect::<Vec<_>>();
 
         curve3.set_line_color("pink");
         curve3.set_line_width(0.5);
@@ -123,11 +112,32 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         add_points_to_curve(&mut curve3, &iter_points);
     }
 
+    let mut curve4 = Curve::new();
+    {
+        let res = Bfgs::new()
+            .with_convergence(Convergence {
+                max_iter: iter_count,
+                ..Default::default()
+            })
+            .minimize(&objective, &[-1.4, 1.])?;
+        let iter_points = res
+            .trajectory
+            .iter()
+            .map(|x| DVec2::from_slice(x))
+            .collect::<Vec<_>>();
+
+        curve4.set_line_color("orange");
+        curve4.set_line_width(0.5);
+        curve4.set_label("BFGS");
+        add_points_to_curve(&mut curve4, &iter_points);
+    }
+
     let mut plot = Plot::new();
     plot.add(&contour).set_labels("x", "y");
     plot.add(&curve1);
     plot.add(&curve2);
     plot.add(&curve3);
+    plot.add(&curve4);
     plot.legend();
 
     plot.show("./target/gradient_descent_2.svg")?;
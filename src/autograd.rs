@@ -1,4 +1,5 @@
 pub mod compute_graph;
+pub mod dual;
 
 #[cfg(test)]
 mod tests {
@@ -10,6 +11,7 @@ mod tests {
     use rand::Rng;
 
     use super::*;
+    use crate::test_math_funcs;
 
     #[test]
     fn auto_computational_graph_0() {
@@ -196,4 +198,353 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn auto_grad_diamond_chain_benchmark() {
+        // every level reuses the previous node three times, x_{i+1} = (x_i * x_i) / x_i
+        let depth = 100_000;
+        let x = Node::start(1.5);
+        let mut y = x.clone();
+        for _ in 0..depth {
+            let square = y.clone() * y.clone();
+            y = square / y;
+        }
+
+        let start = std::time::Instant::now();
+        let grads = Node::auto_grad(Rc::new(y));
+        println!(
+            "auto grad of a diamond chain of depth {depth} took {:?}",
+            start.elapsed()
+        );
+
+        assert_eq!(grads.len(), 1);
+        assert_eq!(grads[0].node().id(), x.id());
+        assert_abs_diff_eq!(grads[0].grad(), 1.0, epsilon = 1e-6);
+    }
+
+    #[test]
+    fn auto_grad_with_constants() {
+        let logistic = |z: Node| 1.0 / (1.0 + BasicFn::exp().to_gen_node_fn()(&[(-z).into()]));
+
+        for z in [-2.0, -0.5, 0.0, 1.5, 3.0] {
+            let y = Rc::new(logistic(2.0 * Node::start(z) - 1.0));
+            let grads = Node::auto_grad(y.clone());
+            let s = y.value();
+
+            assert_eq!(grads.len(), 1);
+            assert_abs_diff_eq!(grads[0].grad(), 2.0 * s * (1.0 - s), epsilon = 1e-12);
+        }
+    }
+
+    #[test]
+    fn auto_grad_on_explicit_tape() {
+        use compute_graph::tape::Tape;
+
+        let tape = Tape::new();
+        let x = tape.var(2.0);
+        let y = tape.var(3.0);
+        let unused = tape.var(4.0);
+        let z = Rc::new(x.clone() * y.clone() + x.clone());
+
+        assert!(z.tape().ptr_eq(&tape));
+        assert!(!Node::start(1.0).tape().ptr_eq(&tape));
+        assert_eq!(tape.len(), 5);
+
+        let grads = Node::auto_grad(z);
+        assert_eq!(grads.len(), 2);
+        assert_eq!(grads[0].node().id(), x.id());
+        assert_eq!(grads[0].grad(), 4.0);
+        assert_eq!(grads[1].node().id(), y.id());
+        assert_eq!(grads[1].grad(), 2.0);
+        assert!(grads.iter().all(|g| g.node().id() != unused.id()));
+
+        // ids are per tape, the first node of every tape has id 0
+        let other = Tape::new().var(2.0);
+        assert_eq!(other.id(), x.id());
+        assert!(!other.tape().ptr_eq(x.tape()));
+    }
+
+    #[test]
+    fn truncated_tape_keeps_sweeps_small() {
+        // a start node kept across iterations, as the parameter of a training loop
+        let w = Node::start(2.0);
+        let tape = w.tape().clone();
+        let mark = tape.len();
+        for i in 0..100 {
+            let target = i as f64 / 10.0;
+            let r = &w * &w - target;
+            let loss = &r * &r;
+            // every sweep covers the start node and this iteration only
+            assert_eq!(tape.len(), mark + 3);
+            let grads = Node::auto_grad(Rc::new(loss));
+            assert_abs_diff_eq!(grads[0].grad(), 4.0 * 2.0 * (4.0 - target));
+            tape.truncate(mark);
+        }
+        assert_eq!(tape.len(), mark);
+        assert_eq!(w.value(), 2.0);
+    }
+
+    #[test]
+    #[should_panic(expected = "nodes recorded on different tapes can not be combined")]
+    fn mixing_tapes_panics() {
+        use compute_graph::tape::Tape;
+
+        let x = Node::start(1.0);
+        let y = Tape::new().var(2.0);
+        let _ = &x + &y;
+    }
+
+    #[test]
+    fn hessian_rosenbrock() {
+        let rosenbrock = |x: Node, y: Node, a: f64, b: f64| {
+            let t = y - x.clone() * x.clone();
+            (a - x.clone()) * (a - x) + b * t.clone() * t
+        };
+
+        let mut rng = rand::thread_rng();
+        let (a, b) = (1.0, 100.0);
+
+        for _ in 0..10 {
+            let p = [rng.gen_range(-2.0..2.0), rng.gen_range(-1.0..3.0)];
+            let y = rosenbrock(Node::start(p[0]), Node::start(p[1]), a, b);
+
+            assert_abs_diff_eq!(
+                y.value(),
+                test_math_funcs::rosenbrock(&p, a, b),
+                epsilon = 1e-10
+            );
+
+            let hess = Node::hessian(&y).unwrap();
+            let expected = test_math_funcs::rosenbrock_hessian(&p, a, b);
+            for i in 0..2 {
+                for j in 0..2 {
+                    assert_abs_diff_eq!(hess[i][j], expected[i][j], epsilon = 1e-10);
+                }
+            }
+
+            let v = [rng.gen_range(-1.0..1.0), rng.gen_range(-1.0..1.0)];
+            let hv = Node::hessian_vector_product(&y, &v).unwrap();
+            for i in 0..2 {
+                let expected = expected[i][0] * v[0] + expected[i][1] * v[1];
+                assert_abs_diff_eq!(hv[i], expected, epsilon = 1e-10);
+            }
+        }
+    }
+
+    #[test]
+    fn hessian_matches_differentiated_gradient() {
+        let f = |x: Node, y: Node| {
+            let a = BasicFn::sin().to_gen_node_fn()(&[(x.clone() * y.clone()).into()]);
+            let b = BasicFn::exp().to_gen_node_fn()(&[y.clone().into()]);
+            let c = BasicFn::ln().to_gen_node_fn()(&[(x.clone() * x.clone()).into()]);
+            a * b / (x + y) + c
+        };
+        let grad = |p: [f64; 2]| {
+            let y = Rc::new(f(Node::start(p[0]), Node::start(p[1])));
+            Node::auto_grad(y)
+                .iter()
+                .map(|pair| pair.grad())
+                .collect::<Vec<_>>()
+        };
+
+        let p = [0.7, 1.3];
+        let hess = Node::hessian(&f(Node::start(p[0]), Node::start(p[1]))).unwrap();
+
+        let h = 1e-6;
+        for j in 0..2 {
+            let (mut p_plus, mut p_minus) = (p, p);
+            p_plus[j] += h;
+            p_minus[j] -= h;
+            let (g_plus, g_minus) = (grad(p_plus), grad(p_minus));
+            for i in 0..2 {
+                let expected = (g_plus[i] - g_minus[i]) / (2.0 * h);
+                assert_abs_diff_eq!(hess[i][j], expected, epsilon = 1e-6);
+            }
+        }
+        assert_abs_diff_eq!(hess[0][1], hess[1][0], epsilon = 1e-12);
+    }
+
+    #[test]
+    fn hessian_requires_second_derivatives() {
+        let square_fn = BasicFn::new(
+            Rc::new(|inputs| inputs[0].as_ref().powi(2)),
+            Rc::new(|inputs| vec![2.0 * inputs[0].as_ref()]),
+        );
+        let x = Node::start(3.0);
+        let y = square_fn.to_gen_node_fn()(&[x.into()]);
+
+        assert!(Node::hessian(&y).is_none());
+        assert!(Node::hessian(&(y * 2.0)).is_none());
+        assert_eq!(
+            Node::hessian(&(Node::start(3.0) * 2.0)),
+            Some(vec![vec![0.0]])
+        );
+    }
+
+    #[test]
+    fn node_elementary_methods() {
+        type Pair = (fn(&Node) -> Node, fn(f64) -> f64);
+        let cases: [Pair; 19] = [
+            (|x| x.exp(), |x| x.exp()),
+            (|x| x.ln(), |x| x.ln()),
+            (|x| x.sin(), |x| x.sin()),
+            (|x| x.cos(), |x| x.cos()),
+            (|x| x.tan(), |x| x.tan()),
+            (|x| x.atan(), |x| x.atan()),
+            (|x| x.sinh(), |x| x.sinh()),
+            (|x| x.cosh(), |x| x.cosh()),
+            (|x| x.tanh(), |x| x.tanh()),
+            (|x| x.sqrt(), |x| x.sqrt()),
+            (|x| x.sigmoid(), |x| 1.0 / (1.0 + (-x).exp())),
+            (|x| x.softplus(), |x| x.exp().ln_1p()),
+            (|x| x.ln_1p(), |x| x.ln_1p()),
+            (|x| x.exp_m1(), |x| x.exp_m1()),
+            (|x| x.abs(), |x| x.abs()),
+            (|x| x.powi(3), |x| x.powi(3)),
+            (|x| x.powf(-1.5), |x| x.powf(-1.5)),
+            (|x| x.atan2(0.5), |x| x.atan2(0.5)),
+            (|x| x.hypot(-2.0), |x| x.hypot(-2.0)),
+        ];
+
+        let x = 0.7;
+        for (node_fn, f64_fn) in &cases {
+            let y = node_fn(&Node::start(x));
+            let grad = Node::auto_grad(Rc::new(y.clone()))[0].grad();
+            let expected = crate::derivatives::central_difference(f64_fn, x, 1e-5);
+            assert_abs_diff_eq!(y.value(), f64_fn(x), epsilon = 1e-15);
+            assert_abs_diff_eq!(grad, expected, epsilon = 1e-8);
+
+            let hess = Node::hessian(&y).unwrap()[0][0];
+            let grad_fn = |x: f64| Node::auto_grad(Rc::new(node_fn(&Node::start(x))))[0].grad();
+            let expected = crate::derivatives::central_difference(&grad_fn, x, 1e-5);
+            assert_abs_diff_eq!(hess, expected, epsilon = 1e-6);
+        }
+    }
+
+    #[test]
+    fn node_erf() {
+        for (x, erf) in [
+            (0.0, 0.0),
+            (0.7, 0.6778011938374184),
+            (-0.7, -0.6778011938374184),
+            (2.5, 0.999593047982555),
+            (7.0, 1.0),
+        ] {
+            let y = Node::start(x).erf();
+            let grad = Node::auto_grad(Rc::new(y.clone()))[0].grad();
+            let hess = Node::hessian(&y).unwrap()[0][0];
+            let expected_grad = std::f64::consts::FRAC_2_SQRT_PI * (-x * x).exp();
+
+            assert_abs_diff_eq!(y.value(), erf, epsilon = 1e-15);
+            assert_abs_diff_eq!(grad, expected_grad, epsilon = 1e-15);
+            assert_abs_diff_eq!(hess, -2.0 * x * expected_grad, epsilon = 1e-15);
+        }
+    }
+
+    #[test]
+    fn node_min_max_subgradients() {
+        let a = Node::start(2.0);
+        let b = Node::start(3.0);
+        let y = a.max(b.clone()) + a.min(b.clone()) * 10.0 + a.max(2.0);
+        let grads = Node::auto_grad(Rc::new(y.clone()));
+
+        assert_eq!(y.value(), 3.0 + 20.0 + 2.0);
+        assert_eq!(grads[0].grad(), 10.0 + 0.5);
+        assert_eq!(grads[1].grad(), 1.0);
+    }
+
+    #[test]
+    fn node_reference_operators() {
+        let [x, y, z] = crate::vars![3.0, 4.0, 0.5];
+
+        // f = (x * y + exp(x) * exp(y)) / (4 * y) + z * ln(x) - y / z, written without clones
+        let f = (&x * &y + x.exp() * y.exp()) / (4.0 * &y) + &z * x.ln() - &y / &z;
+        let f_value = (3.0 * 4.0 + (7.0_f64).exp()) / 16.0 + 0.5 * 3.0_f64.ln() - 8.0;
+        assert_abs_diff_eq!(f.value(), f_value, epsilon = 1e-12);
+
+        let rc_x = Rc::new(x.clone());
+        let g = rc_x.clone() * &y - &x / rc_x.clone() + (-&z);
+        assert_abs_diff_eq!(g.value(), 12.0 - 1.0 - 0.5);
+
+        // two Rcs, or an Rc and a scalar, are combined through references
+        let rc_y = Rc::new(y.clone());
+        let g = &*rc_x + &*rc_y + &*rc_x * 2.0 - 2.0 * &*rc_y;
+        assert_abs_diff_eq!(g.value(), 3.0 + 4.0 + 6.0 - 8.0);
+
+        let mut h = x.clone();
+        h += &y;
+        h *= 2.0;
+        h -= z.clone();
+        h /= &y;
+        assert_abs_diff_eq!(h.value(), (2.0 * 7.0 - 0.5) / 4.0);
+
+        let grads = Node::auto_grad(Rc::new(h));
+        assert_abs_diff_eq!(grads[0].grad(), 0.5);
+        assert_abs_diff_eq!(grads[1].grad(), 2.0 / 4.0 - (2.0 * 7.0 - 0.5) / 16.0);
+        assert_abs_diff_eq!(grads[2].grad(), -0.25);
+    }
+
+    #[test]
+    fn node_sum_and_product() {
+        let values = [1.5, -2.0, 0.5, 3.0];
+        let nodes = crate::vars![&values];
+        let sum: Node = nodes.iter().map(|x| x * x).sum();
+        let product: Node = nodes.iter().product();
+        let tape_len = nodes[0].tape().len();
+
+        assert_abs_diff_eq!(sum.value(), values.iter().map(|x| x * x).sum::<f64>());
+        assert_abs_diff_eq!(product.value(), -4.5);
+        // one node per square, a single n-ary sum node and a single product node
+        assert_eq!(tape_len, 4 + 4 + 1 + 1);
+
+        let grads = Node::auto_grad(Rc::new(sum + product));
+        for (pair, x) in grads.iter().zip(values) {
+            assert_abs_diff_eq!(pair.grad(), 2.0 * x + -4.5 / x, epsilon = 1e-12);
+        }
+
+        let zeros = crate::vars![0.0; 3];
+        assert_eq!(zeros.len(), 3);
+        assert_eq!(zeros.iter().sum::<Node>().value(), 0.0);
+        assert_eq!(std::iter::empty::<Node>().sum::<Node>().value(), 0.0);
+        assert_eq!(std::iter::empty::<Node>().product::<Node>().value(), 1.0);
+        assert_eq!(zeros[..1].iter().product::<Node>().id(), zeros[0].id());
+    }
+
+    #[test]
+    fn node_generic_values() {
+        use compute_graph::tape::Tape;
+        use dual::Dual;
+
+        // f32 graphs live on their own tape
+        let tape = Tape::<f32>::current();
+        let (x, y) = (tape.var(1.5), tape.var(-0.5));
+        let f = (&x * &y).exp() + x.powi(2) / 2f32;
+        let grads = Node::auto_grad(Rc::new(f.clone()));
+        let e = (1.5f32 * -0.5).exp();
+        assert_abs_diff_eq!(f.value(), e + 1.125, epsilon = 1e-6);
+        assert_abs_diff_eq!(grads[0].grad(), -0.5 * e + 1.5, epsilon = 1e-6);
+        assert_abs_diff_eq!(grads[1].grad(), 1.5 * e, epsilon = 1e-6);
+        assert_eq!(Node::start(1.0).tape().len(), 1);
+
+        // reverse over forward, the tangent of the gradient is a Hessian row
+        let tape = Tape::<Dual<2>>::new();
+        let x = tape.var(Dual::variable(1.5, 0));
+        let y = tape.var(Dual::variable(-0.5, 1));
+        let f = x.sin() * &y + y.powi(3);
+        let grads = Node::auto_grad(Rc::new(f.clone()));
+        let hess = Node::hessian(&(Node::start(1.5).sin() * Node::start(-0.5))).unwrap();
+        assert_abs_diff_eq!(
+            grads[0].grad().value(),
+            1.5f64.cos() * -0.5,
+            epsilon = 1e-15
+        );
+        assert_abs_diff_eq!(
+            grads[1].grad().value(),
+            1.5f64.sin() + 0.75,
+            epsilon = 1e-15
+        );
+        assert_abs_diff_eq!(grads[0].grad().tangent()[0], hess[0][0], epsilon = 1e-15);
+        assert_abs_diff_eq!(grads[0].grad().tangent()[1], hess[0][1], epsilon = 1e-15);
+        assert_abs_diff_eq!(grads[1].grad().tangent()[1], -3.0, epsilon = 1e-15);
+    }
 }
@@ -1,2 +1,5 @@
 pub mod basic_fn;
+pub mod grad_check;
 pub mod node;
+pub mod tape;
+pub mod tensor;
@@ -2,33 +2,40 @@ use std::fmt::Debug;
 use std::rc::Rc;
 
 use super::node::Node;
+use crate::float::Float;
 
 #[derive(Debug, Clone)]
-pub enum FnInput {
-    Float(f64),
-    Node(Rc<Node>),
+pub enum FnInput<T: Float = f64> {
+    Float(T),
+    Node(Rc<Node<T>>),
 }
 
-impl From<f64> for FnInput {
-    fn from(value: f64) -> Self {
+impl<T: Float> From<T> for FnInput<T> {
+    fn from(value: T) -> Self {
         FnInput::Float(value)
     }
 }
 
-impl From<Rc<Node>> for FnInput {
-    fn from(value: Rc<Node>) -> Self {
+impl<T: Float> From<Rc<Node<T>>> for FnInput<T> {
+    fn from(value: Rc<Node<T>>) -> Self {
         FnInput::Node(value)
     }
 }
 
-impl From<Node> for FnInput {
-    fn from(value: Node) -> Self {
+impl<T: Float> From<Node<T>> for FnInput<T> {
+    fn from(value: Node<T>) -> Self {
         Self::from(Rc::new(value))
     }
 }
 
-impl AsRef<f64> for FnInput {
-    fn as_ref(&self) -> &f64 {
+impl<T: Float> From<&Node<T>> for FnInput<T> {
+    fn from(value: &Node<T>) -> Self {
+        Self::from(value.clone())
+    }
+}
+
+impl<T: Float> AsRef<T> for FnInput<T> {
+    fn as_ref(&self) -> &T {
         match self {
             FnInput::Float(x) => x,
             FnInput::Node(x) => &x.value,
@@ -36,199 +43,469 @@ impl AsRef<f64> for FnInput {
     }
 }
 
-type FloatFnMultiToSingle = Rc<dyn Fn(&[FnInput]) -> f64>;
-type FloatFnMultiToMulti = Rc<dyn Fn(&[FnInput]) -> Vec<f64>>;
-type FloatFnMultiToNode<'a> = Rc<dyn Fn(&[FnInput]) -> Node + 'a>;
+type FloatFnMultiToSingle<T = f64> = Rc<dyn Fn(&[FnInput<T>]) -> T>;
+type FloatFnMultiToMulti<T = f64> = Rc<dyn Fn(&[FnInput<T>]) -> Vec<T>>;
+type FloatFnMultiToNode<'a, T> = Rc<dyn Fn(&[FnInput<T>]) -> Node<T> + 'a>;
 
 #[derive(Clone)]
-pub struct BasicFn {
-    func: FloatFnMultiToSingle,
-    grad: FloatFnMultiToMulti,
+pub struct BasicFn<T: Float = f64> {
+    func: FloatFnMultiToSingle<T>,
+    grad: FloatFnMultiToMulti<T>,
+    hess: Option<FloatFnMultiToMulti<T>>,
     info: Option<String>,
 }
 
 impl BasicFn {
     pub fn new(func: FloatFnMultiToSingle, grad: FloatFnMultiToMulti) -> Self {
+        Self::from_fns(func, grad)
+    }
+}
+
+impl<T: Float> BasicFn<T> {
+    /// As `new`, for functions of any `Float` type.
+    pub fn from_fns(func: FloatFnMultiToSingle<T>, grad: FloatFnMultiToMulti<T>) -> Self {
         Self {
             func,
             grad,
+            hess: None,
             info: None,
         }
     }
 
+    /// Second derivatives of the function, as a row-major `n x n` matrix for `n` inputs.
+    pub fn with_hess(mut self, hess: FloatFnMultiToMulti<T>) -> Self {
+        self.hess = Some(hess);
+        self
+    }
+
     pub fn with_info(mut self, info: &str) -> Self {
         self.info = Some(info.to_string());
         self
     }
 
-    pub fn value_fn(&self) -> FloatFnMultiToSingle {
+    pub fn value_fn(&self) -> FloatFnMultiToSingle<T> {
         self.func.clone()
     }
 
-    pub fn grad_fn(&self) -> FloatFnMultiToMulti {
+    pub fn grad_fn(&self) -> FloatFnMultiToMulti<T> {
         self.grad.clone()
     }
 
-    pub fn to_gen_node_fn(&self) -> FloatFnMultiToNode {
-        Rc::new(|inputs: &[FnInput]| -> Node {
-            let parents = inputs
-                .iter()
-                .filter_map(|x| {
-                    if let FnInput::Node(node) = x {
-                        Some(node.clone())
-                    } else {
-                        None
-                    }
-                })
-                .collect::<Vec<_>>();
+    pub fn hess_fn(&self) -> Option<FloatFnMultiToMulti<T>> {
+        self.hess.clone()
+    }
+
+    pub fn to_gen_node_fn(&self) -> FloatFnMultiToNode<'_, T> {
+        Rc::new(|inputs: &[FnInput<T>]| -> Node<T> {
             let value = (*self.func)(inputs);
 
-            Node::new(parents, value, Some(Rc::new(self.clone())))
+            Node::new(inputs, value, Rc::new(self.clone()))
         })
     }
 
     pub fn exp() -> Self {
-        BasicFn::new(
-            Rc::new(|inputs| {
-                debug_assert!(inputs.len() == 1);
-                inputs[0].as_ref().exp()
-            }),
-            Rc::new(|inputs| {
-                debug_assert!(inputs.len() == 1);
-                vec![inputs[0].as_ref().exp()]
-            }),
-        )
-        .with_info("exp")
+        Self::unary(|x| x.exp(), |x| x.exp(), |x| x.exp(), "exp")
     }
 
     pub fn ln() -> Self {
-        BasicFn::new(
-            Rc::new(|inputs| {
-                debug_assert!(inputs.len() == 1);
-                inputs[0].as_ref().ln()
-            }),
-            Rc::new(|inputs| {
-                debug_assert!(inputs.len() == 1);
-                vec![1.0 / inputs[0].as_ref()]
-            }),
+        Self::unary(
+            |x| x.ln(),
+            |x| T::one() / x,
+            |x| -T::one() / x.powi(2),
+            "ln",
         )
-        .with_info("ln")
     }
 
     pub fn sin() -> Self {
-        BasicFn::new(
-            Rc::new(|inputs| {
-                debug_assert!(inputs.len() == 1);
-                inputs[0].as_ref().sin()
-            }),
-            Rc::new(|inputs| {
-                debug_assert!(inputs.len() == 1);
-                vec![inputs[0].as_ref().cos()]
-            }),
-        )
-        .with_info("sin")
+        Self::unary(|x| x.sin(), |x| x.cos(), |x| -x.sin(), "sin")
     }
 
     pub fn cos() -> Self {
-        BasicFn::new(
-            Rc::new(|inputs| {
-                debug_assert!(inputs.len() == 1);
-                inputs[0].as_ref().cos()
-            }),
-            Rc::new(|inputs| {
-                debug_assert!(inputs.len() == 1);
-                vec![-inputs[0].as_ref().sin()]
-            }),
-        )
-        .with_info("sin")
+        Self::unary(|x| x.cos(), |x| -x.sin(), |x| -x.cos(), "cos")
     }
 
     pub fn neg() -> Self {
-        BasicFn::new(
-            Rc::new(|inputs| {
-                debug_assert!(inputs.len() == 1);
-                -inputs[0].as_ref()
-            }),
-            Rc::new(|inputs| {
-                debug_assert!(inputs.len() == 1);
-                vec![-1.0]
-            }),
-        )
-        .with_info("neg")
+        Self::unary(|x| -x, |_| -T::one(), |_| T::zero(), "neg")
     }
 
     pub fn sum() -> Self {
-        BasicFn::new(
+        Self::from_fns(
             Rc::new(|inputs| {
                 debug_assert!(inputs.len() > 1);
-                inputs.iter().map(|input| input.as_ref()).sum()
+                inputs
+                    .iter()
+                    .fold(T::zero(), |sum, input| sum + *input.as_ref())
             }),
             Rc::new(|inputs| {
                 debug_assert!(inputs.len() > 1);
-                vec![1.0; inputs.len()]
+                vec![T::one(); inputs.len()]
             }),
         )
+        .with_hess(Rc::new(|inputs| {
+            debug_assert!(inputs.len() > 1);
+            vec![T::zero(); inputs.len() * inputs.len()]
+        }))
         .with_info("sum")
     }
 
     pub fn sub() -> Self {
-        BasicFn::new(
-            Rc::new(|inputs| {
-                debug_assert!(inputs.len() == 2);
-                inputs[0].as_ref() - inputs[1].as_ref()
-            }),
-            Rc::new(|inputs| {
-                debug_assert!(inputs.len() == 2);
-                vec![1.0, -1.0]
-            }),
+        Self::binary(
+            |a, b| a - b,
+            |_, _| [T::one(), -T::one()],
+            |_, _| [T::zero(); 4],
+            "sub",
         )
-        .with_info("sub")
     }
 
     pub fn product() -> Self {
-        BasicFn::new(
+        fn product_except<T: Float>(inputs: &[FnInput<T>], skip: &[usize]) -> T {
+            inputs
+                .iter()
+                .enumerate()
+                .filter(|(k, _)| !skip.contains(k))
+                .fold(T::one(), |p, (_, input)| p * *input.as_ref())
+        }
+
+        Self::from_fns(
             Rc::new(|inputs| {
                 debug_assert!(inputs.len() > 1);
-                inputs.iter().map(|input| input.as_ref()).product()
+                product_except(inputs, &[])
             }),
             Rc::new(|inputs| {
                 debug_assert!(inputs.len() > 1);
-                let mut grad = vec![];
-                #[allow(clippy::needless_range_loop)]
-                for i in 0..inputs.len() {
-                    let mut p = 1.0;
-                    for j in 0..inputs.len() {
-                        if j != i {
-                            p *= inputs[j].as_ref();
-                        }
-                    }
-                    grad.push(p);
-                }
-                grad
+                (0..inputs.len())
+                    .map(|i| product_except(inputs, &[i]))
+                    .collect()
             }),
         )
+        .with_hess(Rc::new(|inputs| {
+            debug_assert!(inputs.len() > 1);
+            let n = inputs.len();
+            let mut hess = vec![T::zero(); n * n];
+            for i in 0..n {
+                for j in 0..n {
+                    if j != i {
+                        hess[i * n + j] = product_except(inputs, &[i, j]);
+                    }
+                }
+            }
+            hess
+        }))
         .with_info("product")
     }
 
     pub fn div() -> Self {
-        BasicFn::new(
-            Rc::new(|inputs| {
+        Self::binary(
+            |a, b| a / b,
+            |a, b| [T::one() / b, -a / b.powi(2)],
+            |a, b| {
+                let ab = -T::one() / b.powi(2);
+                [T::zero(), ab, ab, T::from_f64(2.0) * a / b.powi(3)]
+            },
+            "div",
+        )
+    }
+
+    fn unary(
+        func: impl Fn(T) -> T + 'static,
+        grad: impl Fn(T) -> T + 'static,
+        hess: impl Fn(T) -> T + 'static,
+        info: &str,
+    ) -> Self {
+        Self::from_fns(
+            Rc::new(move |inputs| {
+                debug_assert!(inputs.len() == 1);
+                func(*inputs[0].as_ref())
+            }),
+            Rc::new(move |inputs| {
+                debug_assert!(inputs.len() == 1);
+                vec![grad(*inputs[0].as_ref())]
+            }),
+        )
+        .with_hess(Rc::new(move |inputs| {
+            debug_assert!(inputs.len() == 1);
+            vec![hess(*inputs[0].as_ref())]
+        }))
+        .with_info(info)
+    }
+
+    fn binary(
+        func: impl Fn(T, T) -> T + 'static,
+        grad: impl Fn(T, T) -> [T; 2] + 'static,
+        hess: impl Fn(T, T) -> [T; 4] + 'static,
+        info: &str,
+    ) -> Self {
+        Self::from_fns(
+            Rc::new(move |inputs| {
                 debug_assert!(inputs.len() == 2);
-                inputs[0].as_ref() / inputs[1].as_ref()
+                func(*inputs[0].as_ref(), *inputs[1].as_ref())
             }),
-            Rc::new(|inputs| {
+            Rc::new(move |inputs| {
                 debug_assert!(inputs.len() == 2);
-                vec![
-                    1.0 / inputs[1].as_ref(),
-                    -inputs[0].as_ref() / inputs[1].as_ref().powi(2),
-                ]
+                grad(*inputs[0].as_ref(), *inputs[1].as_ref()).to_vec()
             }),
         )
-        .with_info("div")
+        .with_hess(Rc::new(move |inputs| {
+            debug_assert!(inputs.len() == 2);
+            hess(*inputs[0].as_ref(), *inputs[1].as_ref()).to_vec()
+        }))
+        .with_info(info)
+    }
+
+    pub fn powi(n: i32) -> Self {
+        let n_f = T::from_f64(n as f64);
+        Self::unary(
+            move |x| x.powi(n),
+            move |x| {
+                if n == 0 {
+                    T::zero()
+                } else {
+                    n_f * x.powi(n - 1)
+                }
+            },
+            move |x| {
+                if n == 0 || n == 1 {
+                    T::zero()
+                } else {
+                    n_f * (n_f - T::one()) * x.powi(n - 2)
+                }
+            },
+            &format!("powi({n})"),
+        )
+    }
+
+    pub fn powf(p: T) -> Self {
+        let one = T::one();
+        Self::unary(
+            move |x| x.powf(p),
+            move |x| p * x.powf(p - one),
+            move |x| p * (p - one) * x.powf(p - one - one),
+            &format!("powf({p})"),
+        )
+    }
+
+    pub fn sqrt() -> Self {
+        Self::unary(
+            |x| x.sqrt(),
+            |x| T::from_f64(0.5) / x.sqrt(),
+            |x| T::from_f64(-0.25) / (x * x.sqrt()),
+            "sqrt",
+        )
+    }
+
+    pub fn tan() -> Self {
+        Self::unary(
+            |x| x.tan(),
+            |x| T::one() + x.tan().powi(2),
+            |x| T::from_f64(2.0) * x.tan() * (T::one() + x.tan().powi(2)),
+            "tan",
+        )
+    }
+
+    pub fn atan() -> Self {
+        Self::unary(
+            |x| x.atan(),
+            |x| T::one() / (T::one() + x * x),
+            |x| T::from_f64(-2.0) * x / (T::one() + x * x).powi(2),
+            "atan",
+        )
+    }
+
+    /// `atan2(y, x)` with inputs `[y, x]`, as `f64::atan2`.
+    pub fn atan2() -> Self {
+        Self::binary(
+            |y, x| y.atan2(x),
+            |y, x| {
+                let r2 = x * x + y * y;
+                [x / r2, -y / r2]
+            },
+            |y, x| {
+                let r4 = (x * x + y * y).powi(2);
+                let yx = (y * y - x * x) / r4;
+                let xy = T::from_f64(2.0) * x * y / r4;
+                [-xy, yx, yx, xy]
+            },
+            "atan2",
+        )
+    }
+
+    pub fn sinh() -> Self {
+        Self::unary(|x| x.sinh(), |x| x.cosh(), |x| x.sinh(), "sinh")
+    }
+
+    pub fn cosh() -> Self {
+        Self::unary(|x| x.cosh(), |x| x.sinh(), |x| x.cosh(), "cosh")
+    }
+
+    pub fn tanh() -> Self {
+        Self::unary(
+            |x| x.tanh(),
+            |x| T::one() - x.tanh().powi(2),
+            |x| T::from_f64(-2.0) * x.tanh() * (T::one() - x.tanh().powi(2)),
+            "tanh",
+        )
+    }
+
+    /// Logistic function `1 / (1 + exp(-x))`.
+    pub fn sigmoid() -> Self {
+        Self::unary(
+            sigmoid,
+            |x| sigmoid(x) * (T::one() - sigmoid(x)),
+            |x| {
+                let s = sigmoid(x);
+                s * (T::one() - s) * (T::one() - T::from_f64(2.0) * s)
+            },
+            "sigmoid",
+        )
+    }
+
+    /// `ln(1 + exp(x))`, evaluated without overflow for large `x`.
+    pub fn softplus() -> Self {
+        Self::unary(
+            |x| x.max(T::zero()) + (-x.abs()).exp().ln_1p(),
+            sigmoid,
+            |x| sigmoid(x) * (T::one() - sigmoid(x)),
+            "softplus",
+        )
     }
+
+    pub fn log1p() -> Self {
+        Self::unary(
+            |x| x.ln_1p(),
+            |x| T::one() / (T::one() + x),
+            |x| -T::one() / (T::one() + x).powi(2),
+            "log1p",
+        )
+    }
+
+    pub fn expm1() -> Self {
+        Self::unary(|x| x.exp_m1(), |x| x.exp(), |x| x.exp(), "expm1")
+    }
+
+    /// Absolute value, the subgradient at zero is taken as zero.
+    pub fn abs() -> Self {
+        Self::unary(
+            |x| x.abs(),
+            |x| {
+                if x == T::zero() {
+                    T::zero()
+                } else {
+                    x.signum()
+                }
+            },
+            |_| T::zero(),
+            "abs",
+        )
+    }
+
+    /// Minimum of two inputs, on ties the subgradient is split evenly between them.
+    pub fn min() -> Self {
+        let half = T::from_f64(0.5);
+        Self::binary(
+            |a, b| a.min(b),
+            move |a, b| {
+                if a < b {
+                    [T::one(), T::zero()]
+                } else if a > b {
+                    [T::zero(), T::one()]
+                } else {
+                    [half, half]
+                }
+            },
+            |_, _| [T::zero(); 4],
+            "min",
+        )
+    }
+
+    /// Maximum of two inputs, on ties the subgradient is split evenly between them.
+    pub fn max() -> Self {
+        let half = T::from_f64(0.5);
+        Self::binary(
+            |a, b| a.max(b),
+            move |a, b| {
+                if a > b {
+                    [T::one(), T::zero()]
+                } else if a < b {
+                    [T::zero(), T::one()]
+                } else {
+                    [half, half]
+                }
+            },
+            |_, _| [T::zero(); 4],
+            "max",
+        )
+    }
+
+    /// `sqrt(x^2 + y^2)`, the subgradient at the origin is taken as zero.
+    pub fn hypot() -> Self {
+        Self::binary(
+            |x, y| x.hypot(y),
+            |x, y| {
+                let r = x.hypot(y);
+                if r == T::zero() {
+                    [T::zero(); 2]
+                } else {
+                    [x / r, y / r]
+                }
+            },
+            |x, y| {
+                let r3 = x.hypot(y).powi(3);
+                if r3 == T::zero() {
+                    [T::zero(); 4]
+                } else {
+                    [y * y / r3, -x * y / r3, -x * y / r3, x * x / r3]
+                }
+            },
+            "hypot",
+        )
+    }
+
+    /// Gauss error function.
+    pub fn erf() -> Self {
+        let c = T::from_f64(std::f64::consts::FRAC_2_SQRT_PI);
+        Self::unary(
+            erf,
+            move |x| c * (-x * x).exp(),
+            move |x| T::from_f64(-2.0) * x * c * (-x * x).exp(),
+            "erf",
+        )
+    }
+}
+
+fn sigmoid<T: Float>(x: T) -> T {
+    if x >= T::zero() {
+        T::one() / (T::one() + (-x).exp())
+    } else {
+        let e = x.exp();
+        e / (T::one() + e)
+    }
+}
+
+/// `erf(x) = 2/sqrt(pi) exp(-x^2) sum_n 2^n x^(2n+1) / (1 * 3 * ... * (2n+1))`,
+/// a series of positive terms, and `erf(x) = sign(x)` in double precision beyond `|x| = 6`.
+fn erf<T: Float>(x: T) -> T {
+    if x.is_nan() {
+        return x;
+    }
+    if x.abs() > T::from_f64(6.0) {
+        return x.signum();
+    }
+
+    let two = T::from_f64(2.0);
+    let x2 = x * x;
+    let mut term = x;
+    let mut sum = x;
+    let mut n = T::zero();
+    while term.abs() > T::epsilon() * sum.abs() {
+        n = n + T::one();
+        term = term * two * x2 / (two * n + T::one());
+        sum = sum + term;
+    }
+
+    T::from_f64(std::f64::consts::FRAC_2_SQRT_PI) * (-x2).exp() * sum
 }
 
-impl Debug for BasicFn {
+impl<T: Float> Debug for BasicFn<T> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         if let Some(s) = &self.info {
             write!(f, "{s}")?;
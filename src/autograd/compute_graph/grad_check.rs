@@ -0,0 +1,274 @@
+use std::fmt::Display;
+
+use super::basic_fn::{BasicFn, FnInput};
+use crate::derivatives::{central_difference, Difference};
+
+/// One partial derivative whose closure disagrees with finite differences.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GradMismatch {
+    /// Tape id of the node the function was applied at, `None` for a direct check.
+    pub node: Option<usize>,
+    pub info: String,
+    pub inputs: Vec<f64>,
+    pub argument: usize,
+    pub analytic: f64,
+    pub numeric: f64,
+    /// `|analytic - numeric| / max(1, |analytic|, |numeric|)`
+    pub error: f64,
+}
+
+/// A grad closure that returned a different number of partials than it got inputs.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArityMismatch {
+    /// Tape id of the node the function was applied at, `None` for a direct check.
+    pub node: Option<usize>,
+    pub info: String,
+    pub inputs: Vec<f64>,
+    pub partials: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct GradCheckReport {
+    pub mismatches: Vec<GradMismatch>,
+    pub arity_mismatches: Vec<ArityMismatch>,
+}
+
+impl GradCheckReport {
+    pub fn is_empty(&self) -> bool {
+        self.mismatches.is_empty() && self.arity_mismatches.is_empty()
+    }
+
+    pub(super) fn into_result(self) -> Result<(), Self> {
+        if self.is_empty() {
+            Ok(())
+        } else {
+            Err(self)
+        }
+    }
+}
+
+impl BasicFn {
+    /// Compares the grad closure with central differences of the value closure at `inputs`.
+    pub fn check_grad(&self, inputs: &[f64], tol: f64) -> Result<(), GradCheckReport> {
+        let mut report = GradCheckReport::default();
+        self.check_grad_into(None, inputs, tol, &mut report);
+        report.into_result()
+    }
+
+    pub(super) fn check_grad_into(
+        &self,
+        node: Option<usize>,
+        inputs: &[f64],
+        tol: f64,
+        report: &mut GradCheckReport,
+    ) {
+        let to_fn_inputs = |x: &[f64]| x.iter().map(|&x| FnInput::from(x)).collect::<Vec<_>>();
+        let value_fn = self.value_fn();
+        let analytic = self.grad_fn()(&to_fn_inputs(inputs));
+        if analytic.len() != inputs.len() {
+            report.arity_mismatches.push(ArityMismatch {
+                node,
+                info: format!("{self:?}"),
+                inputs: inputs.to_vec(),
+                partials: analytic.len(),
+            });
+            return;
+        }
+
+        let mismatches = analytic
+            .into_iter()
+            .enumerate()
+            .filter_map(|(argument, analytic)| {
+                let partial = |t: f64| {
+                    let mut x = inputs.to_vec();
+                    x[argument] = t;
+                    value_fn(&to_fn_inputs(&x))
+                };
+                let x = inputs[argument];
+                let numeric = central_difference(&partial, x, Difference::Central.step(x));
+                let error = (analytic - numeric).abs() / analytic.abs().max(numeric.abs()).max(1.);
+
+                (error > tol || error.is_nan()).then(|| GradMismatch {
+                    node,
+                    info: format!("{self:?}"),
+                    inputs: inputs.to_vec(),
+                    argument,
+                    analytic,
+                    numeric,
+                    error,
+                })
+            });
+        report.mismatches.extend(mismatches);
+    }
+}
+
+impl Display for GradCheckReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let location = |f: &mut std::fmt::Formatter<'_>, node: Option<usize>| match node {
+            Some(node) => write!(f, "  node #{node} "),
+            None => write!(f, "  "),
+        };
+        writeln!(f, "{} gradient mismatches:", self.mismatches.len())?;
+        for m in &self.mismatches {
+            location(f, m.node)?;
+            writeln!(
+                f,
+                "{} at {:?}, argument {}: analytic {:e}, numeric {:e}, error {:e}",
+                m.info, m.inputs, m.argument, m.analytic, m.numeric, m.error
+            )?;
+        }
+        if !self.arity_mismatches.is_empty() {
+            writeln!(f, "{} arity mismatches:", self.arity_mismatches.len())?;
+        }
+        for m in &self.arity_mismatches {
+            location(f, m.node)?;
+            writeln!(
+                f,
+                "{} at {:?}: {} partials for {} inputs",
+                m.info,
+                m.inputs,
+                m.partials,
+                m.inputs.len()
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for GradCheckReport {}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use super::super::node::Node;
+    use super::*;
+
+    fn wrong_max_fn() -> BasicFn {
+        BasicFn::new(
+            Rc::new(|inputs| inputs[0].as_ref().max(*inputs[1].as_ref())),
+            // derivatives swapped
+            Rc::new(|inputs| {
+                if inputs[0].as_ref() > inputs[1].as_ref() {
+                    vec![0.0, 1.0]
+                } else {
+                    vec![1.0, 0.0]
+                }
+            }),
+        )
+        .with_info("f64 max")
+    }
+
+    #[test]
+    fn check_builtin_grads() {
+        let unary = [
+            BasicFn::exp(),
+            BasicFn::ln(),
+            BasicFn::sin(),
+            BasicFn::cos(),
+            BasicFn::neg(),
+            BasicFn::powi(3),
+            BasicFn::powi(-2),
+            BasicFn::powf(1.7),
+            BasicFn::sqrt(),
+            BasicFn::tan(),
+            BasicFn::atan(),
+            BasicFn::sinh(),
+            BasicFn::cosh(),
+            BasicFn::tanh(),
+            BasicFn::sigmoid(),
+            BasicFn::softplus(),
+            BasicFn::log1p(),
+            BasicFn::expm1(),
+            BasicFn::abs(),
+            BasicFn::erf(),
+        ];
+        for f in &unary {
+            for x in [0.7, 2.3] {
+                assert_eq!(f.check_grad(&[x], 1e-7), Ok(()), "{f:?}");
+            }
+        }
+        for f in [BasicFn::sum(), BasicFn::product()] {
+            assert_eq!(f.check_grad(&[0.7, -1.3, 2.1], 1e-7), Ok(()), "{f:?}");
+        }
+        for f in [
+            BasicFn::sub(),
+            BasicFn::div(),
+            BasicFn::atan2(),
+            BasicFn::min(),
+            BasicFn::max(),
+            BasicFn::hypot(),
+        ] {
+            assert_eq!(f.check_grad(&[0.7, -1.3], 1e-7), Ok(()), "{f:?}");
+        }
+    }
+
+    #[test]
+    fn check_grad_reports_arguments() {
+        let report = wrong_max_fn().check_grad(&[3.0, 2.0], 1e-6).unwrap_err();
+
+        assert_eq!(report.mismatches.len(), 2);
+        assert_eq!(report.mismatches[0].argument, 0);
+        assert_eq!(report.mismatches[0].analytic, 0.0);
+        assert!((report.mismatches[0].numeric - 1.0).abs() < 1e-8);
+        assert_eq!(report.mismatches[1].argument, 1);
+        assert!(report.mismatches.iter().all(|m| m.node.is_none()));
+        assert!(report.arity_mismatches.is_empty());
+
+        let lines = report.to_string();
+        let lines = lines.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0], "2 gradient mismatches:");
+        assert!(lines[1].starts_with("  f64 max at [3.0, 2.0], argument 0: analytic 0e0"));
+        assert!(lines[2].starts_with("  f64 max at [3.0, 2.0], argument 1: analytic 1e0"));
+    }
+
+    #[test]
+    fn check_grad_reports_arity() {
+        let short = BasicFn::new(
+            Rc::new(|inputs| inputs[0].as_ref() * inputs[1].as_ref()),
+            Rc::new(|inputs| vec![*inputs[1].as_ref()]),
+        )
+        .with_info("short product");
+        let report = short.check_grad(&[3.0, 2.0], 1e-6).unwrap_err();
+        assert!(report.mismatches.is_empty());
+        assert_eq!(
+            report.arity_mismatches,
+            vec![ArityMismatch {
+                node: None,
+                info: "short product".to_string(),
+                inputs: vec![3.0, 2.0],
+                partials: 1,
+            }]
+        );
+        assert!(report
+            .to_string()
+            .ends_with("  short product at [3.0, 2.0]: 1 partials for 2 inputs\n"));
+
+        let x = Node::start(3.0);
+        let y = &x * 2.0;
+        let bad = short.to_gen_node_fn()(&[x.into(), y.into()]);
+        let report = Node::check_grads(&bad, 1e-6).unwrap_err();
+        assert_eq!(report.arity_mismatches.len(), 1);
+        assert_eq!(report.arity_mismatches[0].node, Some(bad.id()));
+    }
+
+    #[test]
+    fn check_graph_grads() {
+        let x = Node::start(3.0);
+        let y = Node::start(2.0);
+        let good = BasicFn::exp().to_gen_node_fn()(&[(x.clone() * y.clone()).into()]) / y.clone();
+        assert_eq!(Node::check_grads(&good, 1e-6), Ok(()));
+
+        let max_fn = wrong_max_fn();
+        let max = max_fn.to_gen_node_fn()(&[x.clone().into(), y.into()]);
+        let bad = good + max.clone() * x;
+        let report = Node::check_grads(&bad, 1e-6).unwrap_err();
+
+        assert_eq!(report.mismatches.len(), 2);
+        assert!(report
+            .mismatches
+            .iter()
+            .all(|m| m.node == Some(max.id()) && m.info == "f64 max"));
+    }
+}
@@ -1,140 +1,257 @@
-use std::collections::VecDeque;
 use std::fmt::Debug;
 use std::rc::Rc;
 
-use uuid::Uuid;
-
 use super::basic_fn::{BasicFn, FnInput};
+use super::grad_check::GradCheckReport;
+use super::tape::{Entry, Operand, Tape};
+use crate::float::Float;
+
+/// Dense matrix as a vector of rows.
+type Rows<T> = Vec<Vec<T>>;
 
+/// Handle to a value recorded on a `Tape`.
 #[derive(Clone)]
-pub struct Node {
-    func: Option<Rc<BasicFn>>,
-    pub(super) value: f64,
-    parents: Vec<Rc<Node>>,
-    id: Uuid,
+pub struct Node<T: Float = f64> {
+    tape: Tape<T>,
+    index: usize,
+    pub(super) value: T,
 }
 
-impl Node {
-    pub fn new(parents: Vec<Rc<Node>>, value: f64, func: Option<Rc<BasicFn>>) -> Self {
-        let parents_empty = parents.is_empty();
-        let func_none = func.is_none();
-        assert!(!(parents_empty ^ func_none));
+impl<T: Float> Node<T> {
+    /// Records `func` applied to `inputs` on the tape shared by the node inputs.
+    ///
+    /// Panics if the node inputs come from different tapes, such as a `Node::start`
+    /// node and one from `var` of an explicit `Tape`.
+    pub fn new(inputs: &[FnInput<T>], value: T, func: Rc<BasicFn<T>>) -> Self {
+        let mut tape: Option<Tape<T>> = None;
+        let operands = inputs
+            .iter()
+            .map(|input| match input {
+                FnInput::Float(x) => Operand::Constant(*x),
+                FnInput::Node(node) => {
+                    match &tape {
+                        Some(tape) => assert!(
+                            tape.ptr_eq(&node.tape),
+                            "nodes recorded on different tapes can not be combined"
+                        ),
+                        None => tape = Some(node.tape.clone()),
+                    }
+                    Operand::Node(node.index)
+                }
+            })
+            .collect();
+        let tape = tape.unwrap_or_else(Tape::current);
 
-        Self {
-            parents,
+        let index = tape.push(Entry {
+            func: Some(func),
+            operands,
             value,
-            func,
-            id: Uuid::new_v4(),
-        }
+        });
+
+        Self::from_tape(tape, index, value)
     }
 
-    pub fn start(value: f64) -> Self {
-        Self::new(vec![], value, None)
+    pub(super) fn from_tape(tape: Tape<T>, index: usize, value: T) -> Self {
+        Self { tape, index, value }
     }
 
     pub fn is_start(&self) -> bool {
-        self.parents.is_empty() && self.func.is_none()
+        self.tape.inner.borrow().entries[self.index].func.is_none()
     }
 
-    pub fn value(&self) -> f64 {
+    pub fn value(&self) -> T {
         self.value
     }
 
-    pub fn id(&self) -> Uuid {
-        self.id
+    /// Position of the node on its tape.
+    ///
+    /// Ids are only unique within a tape, nodes of different tapes can share one, so
+    /// callers keying on ids also have to compare tapes with `Tape::ptr_eq`.
+    pub fn id(&self) -> usize {
+        self.index
     }
 
-    pub fn grad_value(&self) -> Option<Vec<f64>> {
-        self.func.as_ref().map(|f| {
-            let grads = f.grad_fn()(
-                &self
-                    .parents
-                    .iter()
-                    .map(|x| FnInput::from(x.value()))
-                    .collect::<Vec<_>>(),
-            );
-            assert!(grads.len() == self.parents.len());
-            grads
+    pub fn tape(&self) -> &Tape<T> {
+        &self.tape
+    }
+
+    pub fn parents(&self) -> Vec<Node<T>> {
+        let inner = self.tape.inner.borrow();
+        inner.entries[self.index]
+            .operands
+            .iter()
+            .filter_map(Operand::node_index)
+            .map(|i| Self::from_tape(self.tape.clone(), i, inner.entries[i].value))
+            .collect()
+    }
+
+    /// Partial derivatives of the node function with respect to each of `parents()`.
+    pub fn grad_value(&self) -> Option<Vec<T>> {
+        let inner = self.tape.inner.borrow();
+        let entry = &inner.entries[self.index];
+        entry.partials(&inner.entries).map(|grads| {
+            entry
+                .operands
+                .iter()
+                .zip(grads)
+                .filter_map(|(operand, grad)| operand.node_index().map(|_| grad))
+                .collect()
         })
     }
 
-    pub fn auto_grad(node: Rc<Node>) -> Vec<NodeGradPair> {
-        fn insert_pair(queue: &mut VecDeque<NodeGradPair>, pair: NodeGradPair) {
-            for old_pair in queue.iter_mut() {
-                if old_pair.node().id() == pair.node().id() {
-                    old_pair.grad += pair.grad;
-                    return;
+    /// Reverse-mode gradient of `node` with respect to every start node it depends on.
+    ///
+    /// The result is ordered by node id, which is the order the start nodes were created in.
+    pub fn auto_grad(node: Rc<Node<T>>) -> Vec<NodeGradPair<T>> {
+        let adjoints = node.tape.adjoints(node.index);
+        let inner = node.tape.inner.borrow();
+
+        adjoints
+            .into_iter()
+            .enumerate()
+            .filter_map(|(i, adjoint)| {
+                let entry = &inner.entries[i];
+                match (adjoint, &entry.func) {
+                    (Some(grad), None) => Some(NodeGradPair::new(
+                        Rc::new(Self::from_tape(node.tape.clone(), i, entry.value)),
+                        grad,
+                    )),
+                    _ => None,
                 }
-            }
-            queue.push_back(pair);
-        }
+            })
+            .collect()
+    }
 
-        fn process_one(queue: &mut VecDeque<NodeGradPair>) {
-            let processed_pair = queue.pop_front().unwrap();
-            let processed_node = processed_pair.node().clone();
+    /// Hessian of `node` times `v`, with rows and the entries of `v` ordered like the
+    /// start nodes returned by `auto_grad`.
+    ///
+    /// `None` if a `BasicFn` on the way was built without `with_hess`.
+    pub fn hessian_vector_product(node: &Node<T>, v: &[T]) -> Option<Vec<T>> {
+        let starts = node.tape.starts(&node.tape.adjoints(node.index));
+        node.tape.hessian_vector_product(node.index, &starts, v)
+    }
 
-            if processed_node.is_start() {
-                queue.push_back(processed_pair);
-                return;
-            }
+    /// Dense Hessian of `node`, ordered like the start nodes returned by `auto_grad`.
+    pub fn hessian(node: &Node<T>) -> Option<Vec<Vec<T>>> {
+        Self::hessian_with_starts(node).map(|(_, hess)| hess)
+    }
 
-            processed_node
-                .parents
-                .iter()
-                .zip(processed_node.grad_value().unwrap())
-                .map(|(node, grad)| NodeGradPair::new(node.clone(), grad * processed_pair.grad))
-                .for_each(|pair| insert_pair(queue, pair));
-        }
+    /// `hessian` together with the start nodes its rows and columns belong to, from the
+    /// same sweep.
+    pub fn hessian_with_starts(node: &Node<T>) -> Option<(Vec<Node<T>>, Rows<T>)> {
+        let starts = node.tape.starts(&node.tape.adjoints(node.index));
+        let hess = (0..starts.len())
+            .map(|i| {
+                let mut e = vec![T::zero(); starts.len()];
+                e[i] = T::one();
+                node.tape.hessian_vector_product(node.index, &starts, &e)
+            })
+            .collect::<Option<Vec<_>>>()?;
+        let inner = node.tape.inner.borrow();
+        let nodes = starts
+            .iter()
+            .map(|&i| Self::from_tape(node.tape.clone(), i, inner.entries[i].value))
+            .collect();
+        Some((nodes, hess))
+    }
+}
 
-        let mut grads = VecDeque::new();
-        grads.push_back(NodeGradPair::new(node, 1.0));
+impl Node {
+    /// Start node on the current `f64` tape, other types use `Tape::current().var(value)`.
+    ///
+    /// It can only be combined with nodes of the current tape, not with nodes of an
+    /// explicit `Tape::new()`.
+    pub fn start(value: f64) -> Self {
+        Tape::current().var(value)
+    }
 
-        while grads.iter().any(|pair| !pair.node().is_start()) {
-            for _ in 0..grads.len() {
-                process_one(&mut grads);
+    /// Checks the grad closure of every function `node` depends on against finite
+    /// differences at the recorded inputs.
+    pub fn check_grads(node: &Node, tol: f64) -> Result<(), GradCheckReport> {
+        let inner = node.tape.inner.borrow();
+
+        // entries `node` depends on, found without the partials the check is about
+        let mut reached = vec![false; node.index + 1];
+        reached[node.index] = true;
+        for i in (0..=node.index).rev() {
+            if reached[i] {
+                for j in inner.entries[i]
+                    .operands
+                    .iter()
+                    .filter_map(Operand::node_index)
+                {
+                    reached[j] = true;
+                }
             }
         }
 
-        let mut grads = grads.into_iter().collect::<Vec<_>>();
-        grads.sort_by_key(|a| a.node().id());
-
-        grads
+        let mut report = GradCheckReport::default();
+        for (i, entry) in inner.entries[..=node.index].iter().enumerate() {
+            if let (true, Some(f)) = (reached[i], &entry.func) {
+                let inputs = entry
+                    .inputs(&inner.entries)
+                    .iter()
+                    .map(|x| *x.as_ref())
+                    .collect::<Vec<_>>();
+                f.check_grad_into(Some(i), &inputs, tol, &mut report);
+            }
+        }
+        report.into_result()
     }
 }
 
 #[derive(Clone, Debug)]
-pub struct NodeGradPair {
-    node: Rc<Node>,
-    grad: f64,
+pub struct NodeGradPair<T: Float = f64> {
+    node: Rc<Node<T>>,
+    grad: T,
 }
 
-impl NodeGradPair {
-    pub fn new(node: Rc<Node>, grad: f64) -> Self {
+impl<T: Float> NodeGradPair<T> {
+    pub fn new(node: Rc<Node<T>>, grad: T) -> Self {
         Self { node, grad }
     }
 
-    pub fn node(&self) -> &Node {
+    pub fn node(&self) -> &Node<T> {
         &self.node
     }
 
-    pub fn grad(&self) -> f64 {
+    pub fn grad(&self) -> T {
         self.grad
     }
+
+    /// Gradient with entries ordered like `vars`, zero for the variables `pairs` has no
+    /// entry for because the differentiated node does not depend on them.
+    pub fn gradient(vars: &[Node<T>], pairs: &[NodeGradPair<T>]) -> Vec<T> {
+        vars.iter()
+            .map(|var| {
+                pairs
+                    .binary_search_by_key(&var.id(), |pair| pair.node.id())
+                    .ok()
+                    .filter(|&i| pairs[i].node.tape.ptr_eq(&var.tape))
+                    .map_or(T::zero(), |i| pairs[i].grad)
+            })
+            .collect()
+    }
 }
 
-impl Debug for Node {
+impl<T: Float> Debug for Node<T> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "ComputationGraph Node: {{ BasicFn: ")?;
-        if let Some(func) = &self.func {
+        let inner = self.tape.inner.borrow();
+        let entry = &inner.entries[self.index];
+
+        write!(f, "ComputationGraph Node #{}: {{ BasicFn: ", self.index)?;
+        if let Some(func) = &entry.func {
             func.fmt(f)?;
         } else {
             write!(f, "none")?;
         }
         write!(f, ", Value: {:.3}", self.value)?;
-        writeln!(f, ", Parents: [")?;
-        for p in &self.parents {
-            p.fmt(f)?;
-            write!(f, ", ")?;
+        write!(f, ", Inputs: [")?;
+        for operand in &entry.operands {
+            match operand {
+                Operand::Constant(x) => write!(f, "{x:.3}, ")?,
+                Operand::Node(i) => write!(f, "#{i}, ")?,
+            }
         }
         write!(f, "]}}")?;
 
@@ -142,93 +259,264 @@ impl Debug for Node {
     }
 }
 
-impl std::ops::Add for Node {
-    type Output = Self;
-    fn add(self, rhs: Self) -> Self::Output {
-        BasicFn::sum().to_gen_node_fn()(&[Rc::new(self).into(), Rc::new(rhs).into()])
+/// Elementary functions, named after their `f64` counterparts.
+impl<T: Float> Node<T> {
+    fn apply(func: BasicFn<T>, inputs: &[FnInput<T>]) -> Node<T> {
+        let value = func.value_fn()(inputs);
+        Node::new(inputs, value, Rc::new(func))
     }
-}
 
-impl std::ops::Add<f64> for Node {
-    type Output = Self;
-    fn add(self, rhs: f64) -> Self::Output {
-        BasicFn::sum().to_gen_node_fn()(&[Rc::new(self).into(), rhs.into()])
+    fn apply_unary(&self, func: BasicFn<T>) -> Node<T> {
+        Self::apply(func, &[self.clone().into()])
     }
-}
 
-impl std::ops::Add<Node> for f64 {
-    type Output = Node;
-    fn add(self, rhs: Node) -> Self::Output {
-        BasicFn::sum().to_gen_node_fn()(&[self.into(), Rc::new(rhs).into()])
+    fn apply_binary(&self, func: BasicFn<T>, other: impl Into<FnInput<T>>) -> Node<T> {
+        Self::apply(func, &[self.clone().into(), other.into()])
     }
-}
 
-impl std::ops::Sub for Node {
-    type Output = Self;
-    fn sub(self, rhs: Self) -> Self::Output {
-        BasicFn::sub().to_gen_node_fn()(&[Rc::new(self).into(), Rc::new(rhs).into()])
+    pub fn exp(&self) -> Node<T> {
+        self.apply_unary(BasicFn::exp())
     }
-}
 
-impl std::ops::Sub<f64> for Node {
-    type Output = Self;
-    fn sub(self, rhs: f64) -> Self::Output {
-        BasicFn::sub().to_gen_node_fn()(&[Rc::new(self).into(), rhs.into()])
+    pub fn ln(&self) -> Node<T> {
+        self.apply_unary(BasicFn::ln())
     }
-}
 
-impl std::ops::Sub<Node> for f64 {
-    type Output = Node;
-    fn sub(self, rhs: Node) -> Self::Output {
-        BasicFn::sub().to_gen_node_fn()(&[self.into(), Rc::new(rhs).into()])
+    pub fn sin(&self) -> Node<T> {
+        self.apply_unary(BasicFn::sin())
     }
+
+    pub fn cos(&self) -> Node<T> {
+        self.apply_unary(BasicFn::cos())
+    }
+
+    pub fn tan(&self) -> Node<T> {
+        self.apply_unary(BasicFn::tan())
+    }
+
+    pub fn atan(&self) -> Node<T> {
+        self.apply_unary(BasicFn::atan())
+    }
+
+    pub fn sinh(&self) -> Node<T> {
+        self.apply_unary(BasicFn::sinh())
+    }
+
+    pub fn cosh(&self) -> Node<T> {
+        self.apply_unary(BasicFn::cosh())
+    }
+
+    pub fn tanh(&self) -> Node<T> {
+        self.apply_unary(BasicFn::tanh())
+    }
+
+    pub fn sqrt(&self) -> Node<T> {
+        self.apply_unary(BasicFn::sqrt())
+    }
+
+    pub fn sigmoid(&self) -> Node<T> {
+        self.apply_unary(BasicFn::sigmoid())
+    }
+
+    pub fn softplus(&self) -> Node<T> {
+        self.apply_unary(BasicFn::softplus())
+    }
+
+    pub fn abs(&self) -> Node<T> {
+        self.apply_unary(BasicFn::abs())
+    }
+
+    pub fn erf(&self) -> Node<T> {
+        self.apply_unary(BasicFn::erf())
+    }
+
+    pub fn ln_1p(&self) -> Node<T> {
+        self.apply_unary(BasicFn::log1p())
+    }
+
+    pub fn exp_m1(&self) -> Node<T> {
+        self.apply_unary(BasicFn::expm1())
+    }
+
+    pub fn powi(&self, n: i32) -> Node<T> {
+        self.apply_unary(BasicFn::powi(n))
+    }
+
+    pub fn powf(&self, p: T) -> Node<T> {
+        self.apply_unary(BasicFn::powf(p))
+    }
+
+    /// Four quadrant arctangent of `self` (y) and `other` (x).
+    pub fn atan2(&self, other: impl Into<FnInput<T>>) -> Node<T> {
+        self.apply_binary(BasicFn::atan2(), other)
+    }
+
+    pub fn hypot(&self, other: impl Into<FnInput<T>>) -> Node<T> {
+        self.apply_binary(BasicFn::hypot(), other)
+    }
+
+    pub fn min(&self, other: impl Into<FnInput<T>>) -> Node<T> {
+        self.apply_binary(BasicFn::min(), other)
+    }
+
+    pub fn max(&self, other: impl Into<FnInput<T>>) -> Node<T> {
+        self.apply_binary(BasicFn::max(), other)
+    }
+}
+
+macro_rules! impl_binary_op {
+    ($op:ident, $method:ident, $basic_fn:ident, T, $(($lhs:ty, $rhs:ty)),+) => {
+        $(
+            impl<T: Float> std::ops::$op<$rhs> for $lhs {
+                type Output = Node<T>;
+                fn $method(self, rhs: $rhs) -> Self::Output {
+                    Node::apply(BasicFn::$basic_fn(), &[self.into(), rhs.into()])
+                }
+            }
+        )+
+    };
+    ($op:ident, $method:ident, $basic_fn:ident, $float:ty, $(($lhs:ty, $rhs:ty)),+) => {
+        $(
+            impl std::ops::$op<$rhs> for $lhs {
+                type Output = Node<$float>;
+                fn $method(self, rhs: $rhs) -> Self::Output {
+                    Node::apply(BasicFn::$basic_fn(), &[self.into(), rhs.into()])
+                }
+            }
+        )+
+    };
+}
+
+macro_rules! impl_assign_op {
+    ($op:ident, $method:ident, $binary_op:ident, $binary_method:ident) => {
+        impl<T: Float, R> std::ops::$op<R> for Node<T>
+        where
+            for<'a> &'a Node<T>: std::ops::$binary_op<R, Output = Node<T>>,
+        {
+            fn $method(&mut self, rhs: R) {
+                *self = std::ops::$binary_op::$binary_method(&*self, rhs);
+            }
+        }
+    };
+}
+
+/// Scalars on the left are only supported for the primitive float types, as
+/// coherence rules out a blanket `impl<T: Float> Add<Node<T>> for T`.
+///
+/// `Rc<Node<T>>` is not a local type, so an `Rc` only combines with a `Node` or
+/// `&Node`; pairs of two `Rc`s or an `Rc` and a scalar go through `&*rc`.
+macro_rules! impl_binary_ops {
+    ($op:ident, $method:ident, $basic_fn:ident) => {
+        impl_binary_op!(
+            $op,
+            $method,
+            $basic_fn,
+            T,
+            (Node<T>, Node<T>),
+            (Node<T>, T),
+            (&Node<T>, &Node<T>),
+            (&Node<T>, Node<T>),
+            (Node<T>, &Node<T>),
+            (&Node<T>, T),
+            (Node<T>, Rc<Node<T>>),
+            (Rc<Node<T>>, Node<T>),
+            (&Node<T>, Rc<Node<T>>),
+            (Rc<Node<T>>, &Node<T>)
+        );
+        impl_binary_op!($op, $method, $basic_fn, f64, (f64, Node), (f64, &Node));
+        impl_binary_op!(
+            $op,
+            $method,
+            $basic_fn,
+            f32,
+            (f32, Node<f32>),
+            (f32, &Node<f32>)
+        );
+    };
 }
 
-impl std::ops::Mul for Node {
+impl_binary_ops!(Add, add, sum);
+impl_binary_ops!(Sub, sub, sub);
+impl_binary_ops!(Mul, mul, product);
+impl_binary_ops!(Div, div, div);
+
+impl_assign_op!(AddAssign, add_assign, Add, add);
+impl_assign_op!(SubAssign, sub_assign, Sub, sub);
+impl_assign_op!(MulAssign, mul_assign, Mul, mul);
+impl_assign_op!(DivAssign, div_assign, Div, div);
+
+impl<T: Float> std::ops::Neg for Node<T> {
     type Output = Self;
-    fn mul(self, rhs: Self) -> Self::Output {
-        BasicFn::product().to_gen_node_fn()(&[Rc::new(self).into(), Rc::new(rhs).into()])
+    fn neg(self) -> Self::Output {
+        BasicFn::neg().to_gen_node_fn()(&[self.into()])
     }
 }
 
-impl std::ops::Mul<f64> for Node {
-    type Output = Self;
-    fn mul(self, rhs: f64) -> Self::Output {
-        BasicFn::product().to_gen_node_fn()(&[Rc::new(self).into(), rhs.into()])
+impl<T: Float> std::ops::Neg for &Node<T> {
+    type Output = Node<T>;
+    fn neg(self) -> Self::Output {
+        BasicFn::neg().to_gen_node_fn()(&[self.into()])
     }
 }
 
-impl std::ops::Mul<Node> for f64 {
-    type Output = Node;
-    fn mul(self, rhs: Node) -> Self::Output {
-        BasicFn::product().to_gen_node_fn()(&[self.into(), Rc::new(rhs).into()])
+impl<T: Float> Node<T> {
+    /// Records `func` once over all `nodes`, `identity` is used for empty iterators.
+    fn reduce(func: BasicFn<T>, identity: T, mut nodes: Vec<Node<T>>) -> Node<T> {
+        match nodes.len() {
+            0 => Self::apply(func, &[identity.into(), identity.into()]),
+            1 => nodes.pop().unwrap(),
+            _ => Self::apply(
+                func,
+                &nodes.into_iter().map(FnInput::from).collect::<Vec<_>>(),
+            ),
+        }
     }
 }
 
-impl std::ops::Div for Node {
-    type Output = Self;
-    fn div(self, rhs: Self) -> Self::Output {
-        BasicFn::div().to_gen_node_fn()(&[Rc::new(self).into(), Rc::new(rhs).into()])
+/// Sums into a single n-ary node.
+impl<T: Float> std::iter::Sum for Node<T> {
+    fn sum<I: Iterator<Item = Node<T>>>(iter: I) -> Self {
+        Node::reduce(BasicFn::sum(), T::zero(), iter.collect())
     }
 }
 
-impl std::ops::Div<f64> for Node {
-    type Output = Self;
-    fn div(self, rhs: f64) -> Self::Output {
-        BasicFn::div().to_gen_node_fn()(&[Rc::new(self).into(), rhs.into()])
+impl<'a, T: Float> std::iter::Sum<&'a Node<T>> for Node<T> {
+    fn sum<I: Iterator<Item = &'a Node<T>>>(iter: I) -> Self {
+        Node::reduce(BasicFn::sum(), T::zero(), iter.cloned().collect())
     }
 }
 
-impl std::ops::Div<Node> for f64 {
-    type Output = Node;
-    fn div(self, rhs: Node) -> Self::Output {
-        BasicFn::div().to_gen_node_fn()(&[self.into(), Rc::new(rhs).into()])
+/// Multiplies into a single n-ary node.
+impl<T: Float> std::iter::Product for Node<T> {
+    fn product<I: Iterator<Item = Node<T>>>(iter: I) -> Self {
+        Node::reduce(BasicFn::product(), T::one(), iter.collect())
     }
 }
 
-impl std::ops::Neg for Node {
-    type Output = Self;
-    fn neg(self) -> Self::Output {
-        BasicFn::neg().to_gen_node_fn()(&[Rc::new(self).into()])
+impl<'a, T: Float> std::iter::Product<&'a Node<T>> for Node<T> {
+    fn product<I: Iterator<Item = &'a Node<T>>>(iter: I) -> Self {
+        Node::reduce(BasicFn::product(), T::one(), iter.cloned().collect())
     }
 }
+
+/// Creates start nodes on the current tape.
+///
+/// `vars![1.0, 2.0]` gives an array of nodes, `vars![x; n]` a `Vec` of `n` nodes
+/// starting at `x` and `vars![&values]` one node per element of a slice.
+#[macro_export]
+macro_rules! vars {
+    (&$values:expr) => {
+        $values
+            .iter()
+            .map(|&value| $crate::autograd::compute_graph::node::Node::start(value))
+            .collect::<Vec<_>>()
+    };
+    ($value:expr; $n:expr) => {
+        (0..$n)
+            .map(|_| $crate::autograd::compute_graph::node::Node::start($value))
+            .collect::<Vec<_>>()
+    };
+    ($($value:expr),* $(,)?) => {
+        [$($crate::autograd::compute_graph::node::Node::start($value)),*]
+    };
+}
@@ -0,0 +1,272 @@
+use std::any::{Any, TypeId};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::{Rc, Weak};
+
+#[cfg(feature = "uuid")]
+use uuid::Uuid;
+
+use super::basic_fn::{BasicFn, FnInput};
+use super::node::Node;
+use crate::float::Float;
+
+#[derive(Debug, Clone, Copy)]
+pub(super) enum Operand<T: Float> {
+    Constant(T),
+    Node(usize),
+}
+
+impl<T: Float> Operand<T> {
+    pub(super) fn node_index(&self) -> Option<usize> {
+        match self {
+            Operand::Constant(_) => None,
+            Operand::Node(index) => Some(*index),
+        }
+    }
+}
+
+pub(super) struct Entry<T: Float> {
+    pub(super) func: Option<Rc<BasicFn<T>>>,
+    pub(super) operands: Vec<Operand<T>>,
+    pub(super) value: T,
+}
+
+impl<T: Float> Entry<T> {
+    /// Operand values in call order, as the `BasicFn` closures expect them.
+    pub(super) fn inputs(&self, entries: &[Entry<T>]) -> Vec<FnInput<T>> {
+        self.operands
+            .iter()
+            .map(|operand| match operand {
+                Operand::Constant(x) => FnInput::Float(*x),
+                Operand::Node(index) => FnInput::Float(entries[*index].value),
+            })
+            .collect()
+    }
+
+    pub(super) fn partials(&self, entries: &[Entry<T>]) -> Option<Vec<T>> {
+        self.func.as_ref().map(|f| {
+            let grads = f.grad_fn()(&self.inputs(entries));
+            assert!(grads.len() == self.operands.len());
+            grads
+        })
+    }
+}
+
+pub(super) struct TapeInner<T: Float> {
+    pub(super) entries: Vec<Entry<T>>,
+    #[cfg(feature = "uuid")]
+    id: Uuid,
+}
+
+/// Arena recording every node of a compute graph in creation order.
+///
+/// Nodes refer to their inputs by index, so the recording order is already a
+/// topological order and the backward pass is a single reverse sweep.
+#[derive(Clone)]
+pub struct Tape<T: Float = f64> {
+    pub(super) inner: Rc<RefCell<TapeInner<T>>>,
+}
+
+thread_local! {
+    /// Weak handles to the current tape of each tape type, keyed by the type of its storage.
+    static CURRENT_TAPES: RefCell<HashMap<TypeId, Box<dyn Any>>> = RefCell::new(HashMap::new());
+}
+
+/// The current tape storage of type `S` on this thread, created by `new` once every
+/// handle to the previous one is dropped.
+pub(super) fn current<S: 'static>(new: impl FnOnce() -> Rc<RefCell<S>>) -> Rc<RefCell<S>> {
+    CURRENT_TAPES.with(|tapes| {
+        let mut tapes = tapes.borrow_mut();
+        let current = tapes
+            .entry(TypeId::of::<S>())
+            .or_insert_with(|| Box::new(Weak::<RefCell<S>>::new()))
+            .downcast_mut::<Weak<RefCell<S>>>()
+            .expect("tapes are keyed by their storage type");
+        if let Some(inner) = current.upgrade() {
+            return inner;
+        }
+        let inner = new();
+        *current = Rc::downgrade(&inner);
+        inner
+    })
+}
+
+impl<T: Float> Tape<T> {
+    pub fn new() -> Self {
+        Self {
+            inner: Rc::new(RefCell::new(TapeInner {
+                entries: vec![],
+                #[cfg(feature = "uuid")]
+                id: Uuid::new_v4(),
+            })),
+        }
+    }
+
+    /// The tape used by `Node::start` on this thread, one per value type.
+    ///
+    /// It lives as long as any of its nodes, a fresh one is created once all are dropped.
+    /// Loops that keep one of its nodes alive should `truncate` it after every iteration.
+    pub fn current() -> Self {
+        Self {
+            inner: current(|| Self::new().inner),
+        }
+    }
+
+    /// Start node on this tape, it can only be combined with nodes of the same tape.
+    pub fn var(&self, value: T) -> Node<T> {
+        let index = self.push(Entry {
+            func: None,
+            operands: vec![],
+            value,
+        });
+        Node::from_tape(self.clone(), index, value)
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner.borrow().entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Drops every entry from position `len` on, as recorded by a loop body.
+    ///
+    /// The current tape lives as long as any of its nodes, so a start node kept across
+    /// iterations keeps the whole history and `auto_grad` sweeps all of it. Truncating to
+    /// `len()` taken before the loop keeps every sweep the size of one iteration. Nodes
+    /// recorded after `len` must not be used afterwards.
+    pub fn truncate(&self, len: usize) {
+        self.inner.borrow_mut().entries.truncate(len);
+    }
+
+    pub fn ptr_eq(&self, other: &Tape<T>) -> bool {
+        Rc::ptr_eq(&self.inner, &other.inner)
+    }
+
+    #[cfg(feature = "uuid")]
+    pub fn id(&self) -> Uuid {
+        self.inner.borrow().id
+    }
+
+    pub(super) fn push(&self, entry: Entry<T>) -> usize {
+        let mut inner = self.inner.borrow_mut();
+        inner.entries.push(entry);
+        inner.entries.len() - 1
+    }
+
+    /// Adjoints of every entry up to `output`, `None` for entries `output` does not depend on.
+    pub(super) fn adjoints(&self, output: usize) -> Vec<Option<T>> {
+        let inner = self.inner.borrow();
+        let entries = &inner.entries;
+
+        let mut adjoints = vec![None; output + 1];
+        adjoints[output] = Some(T::one());
+
+        for index in (0..=output).rev() {
+            let Some(adjoint) = adjoints[index] else {
+                continue;
+            };
+            let entry = &entries[index];
+            let Some(partials) = entry.partials(entries) else {
+                continue;
+            };
+            entry
+                .operands
+                .iter()
+                .zip(partials)
+                .filter_map(|(operand, grad)| operand.node_index().map(|i| (i, grad)))
+                .for_each(|(i, grad)| {
+                    let sum = adjoints[i].get_or_insert(T::zero());
+                    *sum = *sum + grad * adjoint;
+                });
+        }
+
+        adjoints
+    }
+
+    /// Start entries with an adjoint, in creation order.
+    pub(super) fn starts(&self, adjoints: &[Option<T>]) -> Vec<usize> {
+        let inner = self.inner.borrow();
+        adjoints
+            .iter()
+            .enumerate()
+            .filter(|(i, adjoint)| adjoint.is_some() && inner.entries[*i].func.is_none())
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    /// Hessian of `output` with respect to `starts` applied to `v`, by a forward tangent
+    /// sweep followed by a reverse sweep of adjoints and their tangents.
+    ///
+    /// `None` if a function `output` depends on has no second derivatives.
+    pub(super) fn hessian_vector_product(
+        &self,
+        output: usize,
+        starts: &[usize],
+        v: &[T],
+    ) -> Option<Vec<T>> {
+        assert!(starts.len() == v.len());
+        let inner = self.inner.borrow();
+        let entries = &inner.entries[..=output];
+
+        let mut tangents = vec![T::zero(); entries.len()];
+        starts.iter().zip(v).for_each(|(&i, &v)| tangents[i] = v);
+        let mut partials = vec![None; entries.len()];
+        for (i, entry) in entries.iter().enumerate() {
+            if let Some(grads) = entry.partials(entries) {
+                tangents[i] = entry
+                    .operands
+                    .iter()
+                    .zip(&grads)
+                    .filter_map(|(operand, &grad)| operand.node_index().map(|j| grad * tangents[j]))
+                    .fold(T::zero(), |sum, x| sum + x);
+                partials[i] = Some(grads);
+            }
+        }
+
+        let mut reached = vec![false; entries.len()];
+        let mut adjoints = vec![T::zero(); entries.len()];
+        let mut adjoint_tangents = vec![T::zero(); entries.len()];
+        reached[output] = true;
+        adjoints[output] = T::one();
+
+        for index in (0..entries.len()).rev() {
+            let (Some(grads), true) = (&partials[index], reached[index]) else {
+                continue;
+            };
+            let entry = &entries[index];
+            let hess = entry.func.as_ref()?.hess_fn()?(&entry.inputs(entries));
+            let n = entry.operands.len();
+            assert!(hess.len() == n * n);
+
+            let operand_tangents = entry
+                .operands
+                .iter()
+                .map(|operand| operand.node_index().map_or(T::zero(), |j| tangents[j]))
+                .collect::<Vec<_>>();
+
+            for (k, operand) in entry.operands.iter().enumerate() {
+                let Some(j) = operand.node_index() else {
+                    continue;
+                };
+                let curvature = (0..n)
+                    .map(|l| hess[k * n + l] * operand_tangents[l])
+                    .fold(T::zero(), |sum, x| sum + x);
+                reached[j] = true;
+                adjoints[j] = adjoints[j] + grads[k] * adjoints[index];
+                adjoint_tangents[j] = adjoint_tangents[j]
+                    + grads[k] * adjoint_tangents[index]
+                    + adjoints[index] * curvature;
+            }
+        }
+
+        Some(starts.iter().map(|&i| adjoint_tangents[i]).collect())
+    }
+}
+
+impl<T: Float> Default for Tape<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
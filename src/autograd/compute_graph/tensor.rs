@@ -0,0 +1,462 @@
+use std::cell::RefCell;
+use std::fmt::Debug;
+use std::rc::Rc;
+
+use super::basic_fn::{BasicFn, FnInput};
+use super::tape;
+use crate::float::Float;
+
+/// `(rows, cols)` of a row-major tensor value, vectors are columns `(n, 1)`.
+pub type Shape = (usize, usize);
+
+#[derive(Clone)]
+enum Op<T: Float> {
+    Input,
+    Add(usize, usize),
+    Sub(usize, usize),
+    Mul(usize, usize),
+    Scale(usize, T),
+    Dot(usize, usize),
+    MatMul(usize, usize),
+    Map(usize, Rc<BasicFn<T>>),
+    Sum(usize),
+    Norm(usize),
+}
+
+struct Entry<T: Float> {
+    op: Op<T>,
+    value: Vec<T>,
+    shape: Shape,
+}
+
+type TensorTape<T> = Rc<RefCell<Vec<Entry<T>>>>;
+
+fn sum<T: Float>(values: impl IntoIterator<Item = T>) -> T {
+    values.into_iter().fold(T::zero(), |sum, x| sum + x)
+}
+
+/// Handle to a vector or matrix value recorded on a tensor tape.
+///
+/// Each operation records a single entry holding the whole value, its
+/// vector-Jacobian product is applied once per entry in the backward sweep.
+/// Inputs go on the current tensor tape of their value type, which is kept like
+/// `Tape::current`.
+#[derive(Clone)]
+pub struct TensorNode<T: Float = f64> {
+    tape: TensorTape<T>,
+    index: usize,
+}
+
+impl<T: Float> TensorNode<T> {
+    pub fn vector(values: Vec<T>) -> Self {
+        let n = values.len();
+        Self::matrix(values, (n, 1))
+    }
+
+    pub fn matrix(values: Vec<T>, shape: Shape) -> Self {
+        assert!(values.len() == shape.0 * shape.1);
+        let tape = tape::current(|| Rc::new(RefCell::new(vec![])));
+        Self::push(tape, Op::Input, values, shape)
+    }
+
+    fn push(tape: TensorTape<T>, op: Op<T>, value: Vec<T>, shape: Shape) -> Self {
+        let index = {
+            let mut entries = tape.borrow_mut();
+            entries.push(Entry { op, value, shape });
+            entries.len() - 1
+        };
+        Self { tape, index }
+    }
+
+    fn binary(&self, rhs: &TensorNode<T>) -> (Vec<T>, Vec<T>) {
+        assert!(
+            Rc::ptr_eq(&self.tape, &rhs.tape),
+            "tensors recorded on different tapes can not be combined"
+        );
+        let entries = self.tape.borrow();
+        (
+            entries[self.index].value.clone(),
+            entries[rhs.index].value.clone(),
+        )
+    }
+
+    fn elementwise(&self, rhs: &TensorNode<T>, op: Op<T>, f: impl Fn(T, T) -> T) -> Self {
+        assert!(self.shape() == rhs.shape());
+        let (a, b) = self.binary(rhs);
+        let value = a.iter().zip(&b).map(|(a, b)| f(*a, *b)).collect();
+        Self::push(self.tape.clone(), op, value, self.shape())
+    }
+
+    pub fn value(&self) -> Vec<T> {
+        self.tape.borrow()[self.index].value.clone()
+    }
+
+    pub fn shape(&self) -> Shape {
+        self.tape.borrow()[self.index].shape
+    }
+
+    pub fn len(&self) -> usize {
+        let (rows, cols) = self.shape();
+        rows * cols
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Value of a `(1, 1)` tensor.
+    pub fn scalar(&self) -> T {
+        assert!(self.len() == 1);
+        self.tape.borrow()[self.index].value[0]
+    }
+
+    pub fn id(&self) -> usize {
+        self.index
+    }
+
+    pub fn scale(&self, factor: T) -> Self {
+        let value = self.value().iter().map(|&x| x * factor).collect();
+        Self::push(
+            self.tape.clone(),
+            Op::Scale(self.index, factor),
+            value,
+            self.shape(),
+        )
+    }
+
+    pub fn dot(&self, rhs: &TensorNode<T>) -> Self {
+        assert!(self.len() == rhs.len());
+        let (a, b) = self.binary(rhs);
+        let value = sum(a.iter().zip(&b).map(|(&a, &b)| a * b));
+        Self::push(
+            self.tape.clone(),
+            Op::Dot(self.index, rhs.index),
+            vec![value],
+            (1, 1),
+        )
+    }
+
+    pub fn matmul(&self, rhs: &TensorNode<T>) -> Self {
+        let ((m, k), (k_rhs, n)) = (self.shape(), rhs.shape());
+        assert!(k == k_rhs);
+        let (a, b) = self.binary(rhs);
+        let value = matmul(&a, &b, m, k, n);
+        Self::push(
+            self.tape.clone(),
+            Op::MatMul(self.index, rhs.index),
+            value,
+            (m, n),
+        )
+    }
+
+    /// Applies a single-input `BasicFn` to every element.
+    pub fn map(&self, func: &BasicFn<T>) -> Self {
+        let f = func.value_fn();
+        let value = self
+            .value()
+            .iter()
+            .map(|&x| f(&[FnInput::from(x)]))
+            .collect();
+        Self::push(
+            self.tape.clone(),
+            Op::Map(self.index, Rc::new(func.clone())),
+            value,
+            self.shape(),
+        )
+    }
+
+    pub fn sum(&self) -> Self {
+        let value = sum(self.value());
+        Self::push(self.tape.clone(), Op::Sum(self.index), vec![value], (1, 1))
+    }
+
+    /// Euclidean norm of all elements.
+    pub fn norm(&self) -> Self {
+        let value = sum(self.value().iter().map(|&x| x * x)).sqrt();
+        Self::push(self.tape.clone(), Op::Norm(self.index), vec![value], (1, 1))
+    }
+
+    /// Reverse-mode gradient of a `(1, 1)` tensor with respect to every input tensor it
+    /// depends on, in creation order.
+    pub fn auto_grad(node: &TensorNode<T>) -> Vec<TensorGradPair<T>> {
+        assert!(node.len() == 1, "auto_grad needs a scalar output");
+        let entries = node.tape.borrow();
+
+        let mut adjoints: Vec<Option<Vec<T>>> = vec![None; node.index + 1];
+        adjoints[node.index] = Some(vec![T::one()]);
+
+        let mut grads = vec![];
+        for index in (0..=node.index).rev() {
+            let Some(g) = adjoints[index].take() else {
+                continue;
+            };
+            let entry = &entries[index];
+            let mut accumulate = |i: usize, grad: Vec<T>| {
+                let adjoint = adjoints[i].get_or_insert_with(|| vec![T::zero(); grad.len()]);
+                adjoint.iter_mut().zip(grad).for_each(|(a, g)| *a = *a + g);
+            };
+            let value = |i: usize| &entries[i].value;
+
+            match &entry.op {
+                Op::Input => grads.push(TensorGradPair {
+                    node: Self {
+                        tape: node.tape.clone(),
+                        index,
+                    },
+                    grad: g,
+                }),
+                Op::Add(a, b) => {
+                    accumulate(*a, g.clone());
+                    accumulate(*b, g);
+                }
+                Op::Sub(a, b) => {
+                    accumulate(*b, g.iter().map(|&g| -g).collect());
+                    accumulate(*a, g);
+                }
+                Op::Mul(a, b) => {
+                    accumulate(*a, g.iter().zip(value(*b)).map(|(&g, &b)| g * b).collect());
+                    accumulate(*b, g.iter().zip(value(*a)).map(|(&g, &a)| g * a).collect());
+                }
+                Op::Scale(a, factor) => accumulate(*a, g.iter().map(|&g| g * *factor).collect()),
+                Op::Dot(a, b) => {
+                    accumulate(*a, value(*b).iter().map(|&b| g[0] * b).collect());
+                    accumulate(*b, value(*a).iter().map(|&a| g[0] * a).collect());
+                }
+                Op::MatMul(a, b) => {
+                    let ((m, k), n) = (entries[*a].shape, entries[*b].shape.1);
+                    let b_t = transpose(value(*b), k, n);
+                    let a_t = transpose(value(*a), m, k);
+                    accumulate(*a, matmul(&g, &b_t, m, n, k));
+                    accumulate(*b, matmul(&a_t, &g, k, m, n));
+                }
+                Op::Map(a, func) => {
+                    let f = func.grad_fn();
+                    let grad = g
+                        .iter()
+                        .zip(value(*a))
+                        .map(|(&g, &x)| g * f(&[FnInput::from(x)])[0])
+                        .collect();
+                    accumulate(*a, grad);
+                }
+                Op::Sum(a) => accumulate(*a, vec![g[0]; value(*a).len()]),
+                Op::Norm(a) => {
+                    let norm = entry.value[0];
+                    let grad = value(*a)
+                        .iter()
+                        .map(|&x| {
+                            if norm > T::zero() {
+                                g[0] * x / norm
+                            } else {
+                                T::zero()
+                            }
+                        })
+                        .collect();
+                    accumulate(*a, grad);
+                }
+            }
+        }
+
+        grads.reverse();
+        grads
+    }
+}
+
+fn matmul<T: Float>(a: &[T], b: &[T], m: usize, k: usize, n: usize) -> Vec<T> {
+    let mut c = vec![T::zero(); m * n];
+    for i in 0..m {
+        for l in 0..k {
+            let a_il = a[i * k + l];
+            for j in 0..n {
+                c[i * n + j] = c[i * n + j] + a_il * b[l * n + j];
+            }
+        }
+    }
+    c
+}
+
+fn transpose<T: Float>(a: &[T], rows: usize, cols: usize) -> Vec<T> {
+    let mut t = vec![T::zero(); rows * cols];
+    for i in 0..rows {
+        for j in 0..cols {
+            t[j * rows + i] = a[i * cols + j];
+        }
+    }
+    t
+}
+
+#[derive(Clone, Debug)]
+pub struct TensorGradPair<T: Float = f64> {
+    node: TensorNode<T>,
+    grad: Vec<T>,
+}
+
+impl<T: Float> TensorGradPair<T> {
+    pub fn node(&self) -> &TensorNode<T> {
+        &self.node
+    }
+
+    pub fn grad(&self) -> &[T] {
+        &self.grad
+    }
+}
+
+impl<T: Float> Debug for TensorNode<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (rows, cols) = self.shape();
+        write!(
+            f,
+            "ComputationGraph TensorNode #{}: {{ Shape: {rows}x{cols}, Value: {:.3?} }}",
+            self.index,
+            self.value()
+        )
+    }
+}
+
+impl<T: Float> std::ops::Add for &TensorNode<T> {
+    type Output = TensorNode<T>;
+    fn add(self, rhs: Self) -> Self::Output {
+        self.elementwise(rhs, Op::Add(self.index, rhs.index), |a, b| a + b)
+    }
+}
+
+impl<T: Float> std::ops::Sub for &TensorNode<T> {
+    type Output = TensorNode<T>;
+    fn sub(self, rhs: Self) -> Self::Output {
+        self.elementwise(rhs, Op::Sub(self.index, rhs.index), |a, b| a - b)
+    }
+}
+
+/// Elementwise product.
+impl<T: Float> std::ops::Mul for &TensorNode<T> {
+    type Output = TensorNode<T>;
+    fn mul(self, rhs: Self) -> Self::Output {
+        self.elementwise(rhs, Op::Mul(self.index, rhs.index), |a, b| a * b)
+    }
+}
+
+impl<T: Float> std::ops::Mul<T> for &TensorNode<T> {
+    type Output = TensorNode<T>;
+    fn mul(self, rhs: T) -> Self::Output {
+        self.scale(rhs)
+    }
+}
+
+impl<T: Float> std::ops::Neg for &TensorNode<T> {
+    type Output = TensorNode<T>;
+    fn neg(self) -> Self::Output {
+        self.scale(-T::one())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use approx::assert_abs_diff_eq;
+
+    use super::super::node::Node;
+    use super::*;
+
+    #[test]
+    fn quadratic_form_gradient() {
+        // f(x) = 0.5 x^T A x - b^T x, grad = 0.5 (A + A^T) x - b
+        let a_values = vec![4.0, 1.0, 0.5, 2.0, 3.0, -1.0, 0.0, 1.5, 5.0];
+        let a = TensorNode::matrix(a_values.clone(), (3, 3));
+        let b = TensorNode::vector(vec![1.0, -2.0, 0.5]);
+        let x = TensorNode::vector(vec![0.3, -0.7, 1.1]);
+
+        let f = &x.dot(&a.matmul(&x)).scale(0.5) - &b.dot(&x);
+        let grads = TensorNode::auto_grad(&f);
+        assert_eq!(grads.len(), 3);
+        assert_eq!(grads[2].node().id(), x.id());
+
+        let (xv, bv) = (x.value(), b.value());
+        for i in 0..3 {
+            let expected = (0..3)
+                .map(|j| 0.5 * (a_values[i * 3 + j] + a_values[j * 3 + i]) * xv[j])
+                .sum::<f64>()
+                - bv[i];
+            assert_abs_diff_eq!(grads[2].grad()[i], expected, epsilon = 1e-12);
+        }
+
+        // d/dA = 0.5 x x^T, d/db = -x
+        for i in 0..3 {
+            for j in 0..3 {
+                assert_abs_diff_eq!(grads[0].grad()[i * 3 + j], 0.5 * xv[i] * xv[j]);
+            }
+            assert_abs_diff_eq!(grads[1].grad()[i], -xv[i]);
+        }
+    }
+
+    #[test]
+    fn elementwise_ops_match_scalar_graph() {
+        let values = [0.4, -1.2, 2.5, 0.9];
+        let x = TensorNode::vector(values.to_vec());
+        let y = TensorNode::vector(vec![1.0, 2.0, 3.0, 4.0]);
+        let f = &(&x * &y).map(&BasicFn::sin()).sum() + &(&x - &y).norm();
+        let grads = TensorNode::auto_grad(&f);
+
+        let nodes = values.iter().map(|&v| Node::start(v)).collect::<Vec<_>>();
+        let mut sin_sum = None;
+        let mut square_sum = None;
+        for (i, node) in nodes.iter().enumerate() {
+            let w = (i + 1) as f64;
+            let s = BasicFn::sin().to_gen_node_fn()(&[(node.clone() * w).into()]);
+            let d = node.clone() - w;
+            let sq = d.clone() * d;
+            sin_sum = Some(match sin_sum {
+                Some(acc) => acc + s,
+                None => s,
+            });
+            square_sum = Some(match square_sum {
+                Some(acc) => acc + sq,
+                None => sq,
+            });
+        }
+        let norm_fn = BasicFn::new(
+            Rc::new(|inputs| inputs[0].as_ref().sqrt()),
+            Rc::new(|inputs| vec![0.5 / inputs[0].as_ref().sqrt()]),
+        );
+        let expected = sin_sum.unwrap() + norm_fn.to_gen_node_fn()(&[square_sum.unwrap().into()]);
+        let expected_grads = Node::auto_grad(Rc::new(expected.clone()));
+
+        assert_abs_diff_eq!(f.scalar(), expected.value(), epsilon = 1e-12);
+        for (g, pair) in grads[0].grad().iter().zip(&expected_grads) {
+            assert_abs_diff_eq!(*g, pair.grad(), epsilon = 1e-12);
+        }
+    }
+
+    #[test]
+    fn large_vector_gradient() {
+        let n = 1000;
+        let values = (0..n).map(|i| (i as f64).sin()).collect::<Vec<_>>();
+        let x = TensorNode::vector(values.clone());
+        let f = &x.dot(&x) + &x.map(&BasicFn::exp()).sum();
+        let grads = TensorNode::auto_grad(&f);
+
+        assert_eq!(grads.len(), 1);
+        assert_eq!(grads[0].grad().len(), n);
+        for (g, x) in grads[0].grad().iter().zip(&values) {
+            assert_abs_diff_eq!(*g, 2.0 * x + x.exp(), epsilon = 1e-12);
+        }
+    }
+
+    #[test]
+    fn generic_values_on_separate_tapes() {
+        // f32 tensors get their own current tape, apart from f64 tensors and scalar nodes
+        let x = TensorNode::<f32>::vector(vec![1.0, -2.0, 0.5]);
+        let y = TensorNode::vector(vec![1.0, 2.0]);
+        let node = Node::start(3.0);
+        let f = &x.dot(&x) + &(&x * 2.0).sum();
+        let grads = TensorNode::auto_grad(&f);
+
+        assert_eq!(grads.len(), 1);
+        assert_eq!(grads[0].grad(), &[4.0, -2.0, 3.0]);
+        assert_eq!(y.id(), 0);
+        assert_eq!(node.id(), 0);
+
+        // the current tape is shared while any tensor on it is alive
+        let z = TensorNode::<f32>::vector(vec![1.0, 1.0, 1.0]);
+        assert_eq!((&x - &z).value(), vec![0.0, -3.0, -0.5]);
+    }
+}
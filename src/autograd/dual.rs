@@ -0,0 +1,470 @@
+use std::cmp::Ordering;
+use std::fmt::Display;
+use std::ops::{Add, Div, Mul, Neg, Sub};
+
+use crate::float::Float;
+
+/// Forward-mode dual number carrying a value and `N` tangents.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Dual<const N: usize = 1> {
+    value: f64,
+    tangent: [f64; N],
+}
+
+impl<const N: usize> Dual<N> {
+    pub fn new(value: f64, tangent: [f64; N]) -> Self {
+        Self { value, tangent }
+    }
+
+    pub fn constant(value: f64) -> Self {
+        Self::new(value, [0.0; N])
+    }
+
+    /// Independent variable seeded with the `i`-th unit tangent.
+    pub fn variable(value: f64, i: usize) -> Self {
+        let mut tangent = [0.0; N];
+        tangent[i] = 1.0;
+        Self::new(value, tangent)
+    }
+
+    pub fn value(&self) -> f64 {
+        self.value
+    }
+
+    pub fn tangent(&self) -> &[f64; N] {
+        &self.tangent
+    }
+
+    /// Applies a scalar function with value `value` and derivative `derivative` at `self.value`.
+    fn chain(self, value: f64, derivative: f64) -> Self {
+        Self::new(value, self.tangent.map(|t| t * derivative))
+    }
+
+    pub fn exp(self) -> Self {
+        let value = self.value.exp();
+        self.chain(value, value)
+    }
+
+    pub fn ln(self) -> Self {
+        self.chain(self.value.ln(), 1.0 / self.value)
+    }
+
+    pub fn sin(self) -> Self {
+        self.chain(self.value.sin(), self.value.cos())
+    }
+
+    pub fn cos(self) -> Self {
+        self.chain(self.value.cos(), -self.value.sin())
+    }
+
+    pub fn powi(self, n: i32) -> Self {
+        let derivative = if n == 0 {
+            0.0
+        } else {
+            n as f64 * self.value.powi(n - 1)
+        };
+        self.chain(self.value.powi(n), derivative)
+    }
+}
+
+impl Dual<1> {
+    pub fn var(value: f64) -> Self {
+        Self::variable(value, 0)
+    }
+
+    pub fn derivative(&self) -> f64 {
+        self.tangent[0]
+    }
+}
+
+impl<const N: usize> From<f64> for Dual<N> {
+    fn from(value: f64) -> Self {
+        Self::constant(value)
+    }
+}
+
+impl<const N: usize> Add for Dual<N> {
+    type Output = Self;
+    fn add(self, rhs: Self) -> Self::Output {
+        Self::new(
+            self.value + rhs.value,
+            std::array::from_fn(|i| self.tangent[i] + rhs.tangent[i]),
+        )
+    }
+}
+
+impl<const N: usize> Add<f64> for Dual<N> {
+    type Output = Self;
+    fn add(self, rhs: f64) -> Self::Output {
+        Self::new(self.value + rhs, self.tangent)
+    }
+}
+
+impl<const N: usize> Add<Dual<N>> for f64 {
+    type Output = Dual<N>;
+    fn add(self, rhs: Dual<N>) -> Self::Output {
+        rhs + self
+    }
+}
+
+impl<const N: usize> Sub for Dual<N> {
+    type Output = Self;
+    fn sub(self, rhs: Self) -> Self::Output {
+        Self::new(
+            self.value - rhs.value,
+            std::array::from_fn(|i| self.tangent[i] - rhs.tangent[i]),
+        )
+    }
+}
+
+impl<const N: usize> Sub<f64> for Dual<N> {
+    type Output = Self;
+    fn sub(self, rhs: f64) -> Self::Output {
+        Self::new(self.value - rhs, self.tangent)
+    }
+}
+
+impl<const N: usize> Sub<Dual<N>> for f64 {
+    type Output = Dual<N>;
+    fn sub(self, rhs: Dual<N>) -> Self::Output {
+        -rhs + self
+    }
+}
+
+impl<const N: usize> Mul for Dual<N> {
+    type Output = Self;
+    #[allow(clippy::suspicious_arithmetic_impl)]
+    fn mul(self, rhs: Self) -> Self::Output {
+        Self::new(
+            self.value * rhs.value,
+            std::array::from_fn(|i| self.tangent[i] * rhs.value + self.value * rhs.tangent[i]),
+        )
+    }
+}
+
+impl<const N: usize> Mul<f64> for Dual<N> {
+    type Output = Self;
+    fn mul(self, rhs: f64) -> Self::Output {
+        Self::new(self.value * rhs, self.tangent.map(|t| t * rhs))
+    }
+}
+
+impl<const N: usize> Mul<Dual<N>> for f64 {
+    type Output = Dual<N>;
+    fn mul(self, rhs: Dual<N>) -> Self::Output {
+        rhs * self
+    }
+}
+
+impl<const N: usize> Div for Dual<N> {
+    type Output = Self;
+    #[allow(clippy::suspicious_arithmetic_impl)]
+    fn div(self, rhs: Self) -> Self::Output {
+        let value = self.value / rhs.value;
+        Self::new(
+            value,
+            std::array::from_fn(|i| (self.tangent[i] - value * rhs.tangent[i]) / rhs.value),
+        )
+    }
+}
+
+impl<const N: usize> Div<f64> for Dual<N> {
+    type Output = Self;
+    fn div(self, rhs: f64) -> Self::Output {
+        Self::new(self.value / rhs, self.tangent.map(|t| t / rhs))
+    }
+}
+
+impl<const N: usize> Div<Dual<N>> for f64 {
+    type Output = Dual<N>;
+    fn div(self, rhs: Dual<N>) -> Self::Output {
+        Dual::constant(self) / rhs
+    }
+}
+
+impl<const N: usize> Neg for Dual<N> {
+    type Output = Self;
+    fn neg(self) -> Self::Output {
+        Self::new(-self.value, self.tangent.map(|t| -t))
+    }
+}
+
+/// Ordered by value, the tangents are ignored.
+impl<const N: usize> PartialOrd for Dual<N> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.value.partial_cmp(&other.value)
+    }
+}
+
+impl<const N: usize> Display for Dual<N> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        Display::fmt(&self.value, f)?;
+        write!(f, " + {:?}ε", self.tangent)
+    }
+}
+
+/// Lets the bracketing searches and compute graphs differentiate through their
+/// inputs, `min`, `max` and `abs` pick the tangent of the selected branch.
+impl<const N: usize> Float for Dual<N> {
+    fn from_f64(x: f64) -> Self {
+        Self::constant(x)
+    }
+
+    fn to_f64(self) -> f64 {
+        self.value
+    }
+
+    fn epsilon() -> Self {
+        Self::constant(f64::EPSILON)
+    }
+
+    fn is_finite(self) -> bool {
+        self.value.is_finite() && self.tangent.iter().all(|t| t.is_finite())
+    }
+
+    fn is_nan(self) -> bool {
+        self.value.is_nan()
+    }
+
+    /// The subgradient at zero is taken as zero, like `BasicFn::abs`.
+    fn abs(self) -> Self {
+        if self.value < 0.0 {
+            -self
+        } else if self.value == 0.0 {
+            self.chain(0.0, 0.0)
+        } else {
+            self
+        }
+    }
+
+    fn signum(self) -> Self {
+        Self::constant(self.value.signum())
+    }
+
+    fn max(self, other: Self) -> Self {
+        if other.value.is_nan() || self.value >= other.value {
+            self
+        } else {
+            other
+        }
+    }
+
+    fn min(self, other: Self) -> Self {
+        if other.value.is_nan() || self.value <= other.value {
+            self
+        } else {
+            other
+        }
+    }
+
+    fn sqrt(self) -> Self {
+        let value = self.value.sqrt();
+        self.chain(value, 0.5 / value)
+    }
+
+    fn exp(self) -> Self {
+        Dual::exp(self)
+    }
+
+    fn ln(self) -> Self {
+        Dual::ln(self)
+    }
+
+    fn ln_1p(self) -> Self {
+        self.chain(self.value.ln_1p(), 1.0 / (1.0 + self.value))
+    }
+
+    fn exp_m1(self) -> Self {
+        self.chain(self.value.exp_m1(), self.value.exp())
+    }
+
+    fn powi(self, n: i32) -> Self {
+        Dual::powi(self, n)
+    }
+
+    fn powf(self, p: Self) -> Self {
+        if p.tangent.iter().all(|&t| t == 0.0) {
+            self.chain(
+                self.value.powf(p.value),
+                p.value * self.value.powf(p.value - 1.0),
+            )
+        } else {
+            (p * Dual::ln(self)).exp()
+        }
+    }
+
+    fn sin(self) -> Self {
+        Dual::sin(self)
+    }
+
+    fn cos(self) -> Self {
+        Dual::cos(self)
+    }
+
+    fn tan(self) -> Self {
+        let value = self.value.tan();
+        self.chain(value, 1.0 + value * value)
+    }
+
+    fn atan(self) -> Self {
+        self.chain(self.value.atan(), 1.0 / (1.0 + self.value * self.value))
+    }
+
+    fn atan2(self, x: Self) -> Self {
+        let r2 = x.value * x.value + self.value * self.value;
+        Self::new(
+            self.value.atan2(x.value),
+            std::array::from_fn(|i| (x.value * self.tangent[i] - self.value * x.tangent[i]) / r2),
+        )
+    }
+
+    fn sinh(self) -> Self {
+        self.chain(self.value.sinh(), self.value.cosh())
+    }
+
+    fn cosh(self) -> Self {
+        self.chain(self.value.cosh(), self.value.sinh())
+    }
+
+    fn tanh(self) -> Self {
+        let value = self.value.tanh();
+        self.chain(value, 1.0 - value * value)
+    }
+
+    fn hypot(self, other: Self) -> Self {
+        let r = self.value.hypot(other.value);
+        if r == 0.0 {
+            return Self::constant(r);
+        }
+        Self::new(
+            r,
+            std::array::from_fn(|i| {
+                (self.value * self.tangent[i] + other.value * other.tangent[i]) / r
+            }),
+        )
+    }
+}
+
+/// Derivative of a scalar function at `x`.
+pub fn derivative(f: impl Fn(Dual) -> Dual, x: f64) -> f64 {
+    f(Dual::var(x)).derivative()
+}
+
+/// Derivative of `f` at `x` along the direction `d`, in a single forward pass.
+pub fn directional_derivative(f: impl Fn(&[Dual]) -> Dual, x: &[f64], d: &[f64]) -> f64 {
+    assert!(x.len() == d.len());
+    let inputs = x
+        .iter()
+        .zip(d)
+        .map(|(&x, &d)| Dual::new(x, [d]))
+        .collect::<Vec<_>>();
+    f(&inputs).derivative()
+}
+
+/// Full gradient of `f` at `x`, carrying one tangent per input.
+pub fn gradient<const N: usize>(f: impl Fn(&[Dual<N>; N]) -> Dual<N>, x: &[f64; N]) -> [f64; N] {
+    let inputs = std::array::from_fn(|i| Dual::variable(x[i], i));
+    *f(&inputs).tangent()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use approx::assert_abs_diff_eq;
+    use rand::Rng;
+
+    use super::super::compute_graph::{basic_fn::BasicFn, node::Node};
+    use super::*;
+
+    fn node_fn(f: BasicFn, x: Node) -> Node {
+        f.to_gen_node_fn()(&[x.into()])
+    }
+
+    #[test]
+    fn derivative_matches_auto_grad() {
+        let f_dual = |x: Dual| x * x.sin() + 1.0 / (1.0 + (-x).exp()) - (x * x + 2.0).ln();
+        let f_node = |x: Node| {
+            x.clone() * node_fn(BasicFn::sin(), x.clone())
+                + 1.0 / (1.0 + node_fn(BasicFn::exp(), -x.clone()))
+                - node_fn(BasicFn::ln(), x.clone() * x + 2.0)
+        };
+
+        let n = 20;
+        (0..n)
+            .map(|i| (i as f64 / n as f64) * std::f64::consts::TAU - 3.0)
+            .for_each(|x| {
+                let y = Rc::new(f_node(Node::start(x)));
+                let grads = Node::auto_grad(y.clone());
+
+                assert_abs_diff_eq!(f_dual(Dual::var(x)).value(), y.value(), epsilon = 1e-14);
+                assert_abs_diff_eq!(derivative(f_dual, x), grads[0].grad(), epsilon = 1e-12);
+            });
+    }
+
+    #[test]
+    fn directional_derivative_matches_auto_grad() {
+        let f_dual = |v: &[Dual]| {
+            let (x, y, z) = (v[0], v[1], v[2]);
+            ((x + y).sin() + (y * z).cos() * (x * x - z * z).ln()).exp()
+        };
+        let f_node = |x: Node, y: Node, z: Node| {
+            let a0 = node_fn(BasicFn::sin(), x.clone() + y.clone());
+            let b0 = node_fn(BasicFn::cos(), y * z.clone());
+            let c0 = node_fn(BasicFn::ln(), x.clone() * x - z.clone() * z);
+            node_fn(BasicFn::exp(), a0 + b0 * c0)
+        };
+
+        let mut rng = rand::thread_rng();
+
+        for _ in 0..10 {
+            let z = rng.gen_range(0.1..1.0);
+            let x = [z + rng.gen_range(0.1..1.0), rng.gen_range(2.1..3.9), z];
+            let d = [
+                rng.gen_range(-1.0..1.0),
+                rng.gen_range(-1.0..1.0),
+                rng.gen_range(-1.0..1.0),
+            ];
+
+            let y = f_node(Node::start(x[0]), Node::start(x[1]), Node::start(x[2]));
+            let grads = Node::auto_grad(Rc::new(y));
+            let expected = grads.iter().zip(d).map(|(g, d)| g.grad() * d).sum::<f64>();
+
+            assert_abs_diff_eq!(
+                directional_derivative(f_dual, &x, &d),
+                expected,
+                epsilon = 1e-10
+            );
+
+            let grad = gradient(f_dual_3, &x);
+            for (g, pair) in grad.iter().zip(&grads) {
+                assert_abs_diff_eq!(*g, pair.grad(), epsilon = 1e-10);
+            }
+        }
+
+        fn f_dual_3(v: &[Dual<3>; 3]) -> Dual<3> {
+            let (x, y, z) = (v[0], v[1], v[2]);
+            ((x + y).sin() + (y * z).cos() * (x * x - z * z).ln()).exp()
+        }
+    }
+
+    #[test]
+    fn dual_quotient_and_power_rules() {
+        let x = Dual::var(2.0);
+        assert_eq!((3.0 / x).derivative(), -0.75);
+        assert_eq!((x / 4.0).derivative(), 0.25);
+        assert_eq!((5.0 - x).derivative(), -1.0);
+        assert_eq!(x.powi(3).derivative(), 12.0);
+        assert_eq!(Dual::var(0.0).powi(0).derivative(), 0.0);
+        assert_eq!(Dual::var(0.0).powi(1).derivative(), 1.0);
+    }
+
+    #[test]
+    fn dual_abs_matches_graph_at_zero() {
+        for x in [-0.5, 0.0, 0.5] {
+            let graph = Node::auto_grad(Rc::new(Node::start(x).abs()))[0].grad();
+            assert_eq!(Float::abs(Dual::var(x)).derivative(), graph, "{x}");
+        }
+        assert_eq!(Dual::<2>::constant(1.5).tangent(), &[0.0, 0.0]);
+    }
+}
@@ -1,17 +1,224 @@
-pub fn bracket_minimum(
-    f: &dyn Fn(f64) -> f64,
-    x0: f64,
-    s: f64,
-    k: f64,
+mod line_minimizer;
+
+pub use line_minimizer::{LineMethod, LineMinimizer};
+
+use std::fmt::Display;
+use std::rc::Rc;
+
+use crate::autograd::compute_graph::{node::Node, tape::Tape};
+use crate::float::Float;
+use crate::objective::UnivariateFn;
+
+/// Outcome of a univariate search.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchResult<T: Float = f64> {
+    /// Best point found.
+    pub x: T,
+    pub fx: T,
+    /// Final bracket, ordered.
+    pub bracket: (T, T),
+    /// Number of evaluations of the searched function.
+    pub evaluations: usize,
+    /// Every evaluation `(x, f(x))` in the order it was made.
+    pub history: Vec<(T, T)>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum BracketError<T: Float = f64> {
+    /// The function returned NaN or an infinity.
+    NonFinite { x: T, fx: T },
+    /// `iter_limit` expansions were made without finding a bracket, for minima the
+    /// function was still decreasing.
+    IterationLimit { iterations: usize, x: T, fx: T },
+    /// `max_iter` iterations were made without reaching the tolerance.
+    NotConverged { iterations: usize, x: T, fx: T },
+    /// The function stopped decreasing without turning up, or the step overflowed,
+    /// so it only approaches its infimum asymptotically.
+    MonotoneDecrease { x: T, fx: T },
+    /// The expansion factor `k` has to be greater than 1.
+    InvalidExpansion(T),
+    /// The initial step `s` has to be positive and finite.
+    InvalidStep(T),
+    /// Bracket ends have to be finite and distinct, three point brackets ordered.
+    InvalidBracket(T, T),
+    /// Tolerances and Lipschitz constants have to be positive.
+    InvalidParameter { name: &'static str, value: T },
+    /// The function, or for `derivative_bisection` its derivative, has the same sign at
+    /// both bracket ends.
+    NoSignChange { fa: T, fb: T },
+    /// A secant or Newton step is undefined since the slope vanished.
+    ZeroSlope { x: T, fx: T },
+    /// Two evaluations differ by more than the Lipschitz constant allows.
+    LipschitzViolation { a: (T, T), b: (T, T) },
+}
+
+impl<T: Float> Display for BracketError<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NonFinite { x, fx } => write!(f, "non-finite value {fx} at x = {x}"),
+            Self::IterationLimit { iterations, x, fx } => write!(
+                f,
+                "no bracket after {iterations} expansions, last at f({x}) = {fx}"
+            ),
+            Self::NotConverged { iterations, x, fx } => write!(
+                f,
+                "not converged after {iterations} iterations, f({x}) = {fx}"
+            ),
+            Self::MonotoneDecrease { x, fx } => {
+                write!(f, "function decreases monotonically, f({x}) = {fx}")
+            }
+            Self::InvalidExpansion(k) => write!(f, "expansion factor {k} is not greater than 1"),
+            Self::InvalidStep(s) => write!(f, "step {s} is not positive and finite"),
+            Self::InvalidBracket(a, b) => write!(f, "invalid bracket ({a}, {b})"),
+            Self::InvalidParameter { name, value } => {
+                write!(f, "{name} = {value} is not positive")
+            }
+            Self::NoSignChange { fa, fb } => write!(
+                f,
+                "function does not change sign over the bracket ({fa}, {fb})"
+            ),
+            Self::ZeroSlope { x, fx } => write!(f, "zero slope at f({x}) = {fx}"),
+            Self::LipschitzViolation { a, b } => write!(
+                f,
+                "Lipschitz constant exceeded between f({}) = {} and f({}) = {}",
+                a.0, a.1, b.0, b.1
+            ),
+        }
+    }
+}
+
+impl<T: Float> std::error::Error for BracketError<T> {}
+
+/// Wraps the searched function to record its evaluations and reject non-finite values.
+pub(crate) struct Recorder<'a, T: Float> {
+    f: &'a dyn UnivariateFn<T>,
+    history: Vec<(T, T)>,
+}
+
+impl<'a, T: Float> Recorder<'a, T> {
+    pub(crate) fn new(f: &'a dyn UnivariateFn<T>) -> Self {
+        Self { f, history: vec![] }
+    }
+
+    pub(crate) fn eval(&mut self, x: T) -> Result<T, BracketError<T>> {
+        let fx = self.f.value(x);
+        self.history.push((x, fx));
+        if fx.is_finite() {
+            Ok(fx)
+        } else {
+            Err(BracketError::NonFinite { x, fx })
+        }
+    }
+
+    /// `eval` that also returns the derivative, the history only holds the value.
+    pub(crate) fn eval_with_derivative(&mut self, x: T) -> Result<(T, T), BracketError<T>> {
+        let (fx, dfx) = self.f.value_and_derivative(x);
+        self.history.push((x, fx));
+        if fx.is_finite() {
+            Ok((fx, dfx))
+        } else {
+            Err(BracketError::NonFinite { x, fx })
+        }
+    }
+
+    pub(crate) fn finish(self, x: T, fx: T, bracket: (T, T)) -> SearchResult<T> {
+        SearchResult {
+            x,
+            fx,
+            bracket: ordered(bracket),
+            evaluations: self.history.len(),
+            history: self.history,
+        }
+    }
+}
+
+fn ordered<T: Float>((a, b): (T, T)) -> (T, T) {
+    if a <= b {
+        (a, b)
+    } else {
+        (b, a)
+    }
+}
+
+pub(crate) fn check_bracket<T: Float>(bracket: (T, T)) -> Result<(T, T), BracketError<T>> {
+    let (a, b) = bracket;
+    if a.is_finite() && b.is_finite() && a != b {
+        Ok(ordered(bracket))
+    } else {
+        Err(BracketError::InvalidBracket(a, b))
+    }
+}
+
+/// Initial step `s` and growth factor `k` of an expanding search.
+pub(crate) fn check_expansion<T: Float>(s: T, k: T) -> Result<(), BracketError<T>> {
+    if k.is_nan() || k <= T::one() {
+        return Err(BracketError::InvalidExpansion(k));
+    }
+    if !(s > T::zero() && s.is_finite()) {
+        return Err(BracketError::InvalidStep(s));
+    }
+    Ok(())
+}
+
+pub(crate) fn check_positive(name: &'static str, value: f64) -> Result<(), BracketError> {
+    if value > 0. {
+        Ok(())
+    } else {
+        Err(BracketError::InvalidParameter { name, value })
+    }
+}
+
+/// Width below which an interval search stops.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Tolerance {
+    Absolute(f64),
+    /// Relative to `|x|`, absolute for `|x|` below 1.
+    Relative(f64),
+}
+
+impl Tolerance {
+    /// Allowed interval width around `x`.
+    pub fn width(&self, x: f64) -> f64 {
+        match *self {
+            Self::Absolute(tol) => tol,
+            Self::Relative(tol) => tol * x.abs().max(1.),
+        }
+    }
+
+    /// Allowed width anywhere inside `bracket`.
+    fn width_over(&self, (a, b): (f64, f64)) -> f64 {
+        let x = if a <= 0. && 0. <= b {
+            0.
+        } else {
+            a.abs().min(b.abs())
+        };
+        self.width(x)
+    }
+
+    pub(crate) fn check(&self) -> Result<(), BracketError> {
+        match *self {
+            Self::Absolute(tol) | Self::Relative(tol) => check_positive("tol", tol),
+        }
+    }
+}
+
+/// Expands steps of `s`, growing by `k`, from `x0` downhill until the function turns up.
+///
+/// `x` of the result is the lowest point found, inside the returned bracket.
+pub fn bracket_minimum<T: Float>(
+    f: &dyn UnivariateFn<T>,
+    x0: T,
+    s: T,
+    k: T,
     iter_limit: usize,
-) -> Option<(f64, f64)> {
-    assert!(k > 1.);
-    assert!(s > 0.);
+) -> Result<SearchResult<T>, BracketError<T>> {
+    check_expansion(s, k)?;
 
-    let (mut a, ya) = (x0, f(x0));
-    let (mut b, mut yb) = (a + s, f(a + s));
+    let mut f = Recorder::new(f);
+    let (mut a, ya) = (x0, f.eval(x0)?);
+    let (mut b, mut yb) = (a + s, f.eval(a + s)?);
     let mut s = s;
-    let mut iter_limit = iter_limit;
+    let mut flat = false;
 
     if yb > ya {
         (a, b) = (b, a);
@@ -19,43 +226,106 @@ pub fn bracket_minimum(
         s = -s;
     }
 
-    while iter_limit > 0 {
-        let (c, yc) = (b + s, f(b + s));
+    for _ in 0..iter_limit {
+        let c = b + s;
+        if !c.is_finite() {
+            return Err(BracketError::MonotoneDecrease { x: b, fx: yb });
+        }
+        let yc = f.eval(c)?;
         if yc > yb {
-            return if a < c { Some((a, c)) } else { Some((c, a)) };
+            return Ok(f.finish(b, yb, (a, c)));
         }
+        flat = yc == yb;
         (a, b, yb) = (b, c, yc);
-        s *= k;
+        s = s * k;
+    }
+
+    Err(if flat {
+        BracketError::MonotoneDecrease { x: b, fx: yb }
+    } else {
+        BracketError::IterationLimit {
+            iterations: iter_limit,
+            x: b,
+            fx: yb,
+        }
+    })
+}
+
+/// `bracket_minimum` within `bounds`, the expansion is clipped at the bounds and `x0`
+/// is clamped into them.
+///
+/// When `f` still decreases at a bound, `x` of the result is that bound and the
+/// bracket ends there; `LineMinimizer::with_bounds` handles both cases.
+pub fn bracket_minimum_bounded<T: Float>(
+    f: &dyn UnivariateFn<T>,
+    x0: T,
+    s: T,
+    k: T,
+    bounds: (T, T),
+    iter_limit: usize,
+) -> Result<SearchResult<T>, BracketError<T>> {
+    check_expansion(s, k)?;
+    let (lo, hi) = check_bracket(bounds)?;
+    let clamp = |x: T| x.max(lo).min(hi);
 
-        iter_limit -= 1;
+    let mut f = Recorder::new(f);
+    let x0 = clamp(x0);
+    let mut s = if x0 == hi { -s } else { s };
+    let (mut a, ya) = (x0, f.eval(x0)?);
+    let (mut b, mut yb) = (clamp(a + s), f.eval(clamp(a + s))?);
+
+    if yb > ya {
+        (a, b) = (b, a);
+        yb = ya;
+        s = -s;
     }
 
-    None
+    for _ in 0..iter_limit {
+        let c = clamp(b + s);
+        if c == b {
+            // decreasing up to the bound
+            return Ok(f.finish(b, yb, (a, b)));
+        }
+        let yc = f.eval(c)?;
+        if yc > yb {
+            return Ok(f.finish(b, yb, (a, c)));
+        }
+        (a, b, yb) = (b, c, yc);
+        s = s * k;
+    }
+
+    Err(BracketError::IterationLimit {
+        iterations: iter_limit,
+        x: b,
+        fx: yb,
+    })
 }
 
-pub fn fibonacci_search(
-    f: &dyn Fn(f64) -> f64,
-    bracket: (f64, f64),
+pub fn fibonacci_search<T: Float>(
+    f: &dyn UnivariateFn<T>,
+    bracket: (T, T),
     search_count: usize,
-    eps: f64,
-) -> (f64, f64) {
-    let (mut a, mut b) = bracket;
-    assert!(a < b);
+    eps: T,
+) -> Result<SearchResult<T>, BracketError<T>> {
+    let (mut a, mut b) = check_bracket(bracket)?;
+    let mut f = Recorder::new(f);
 
-    let s = (1. - 5f64.sqrt()) / (1. + 5f64.sqrt());
-    let phi = (1. + 5f64.sqrt()) / 2.;
-    let rho = |i: usize| (1. - s.powi(i as i32)) / (phi * (1. - s.powi(i as i32 + 1)));
+    let one = T::one();
+    let sqrt5 = T::from_f64(5.).sqrt();
+    let s = (one - sqrt5) / (one + sqrt5);
+    let phi = (one + sqrt5) / T::from_f64(2.);
+    let rho = |i: usize| (one - s.powi(i as i32)) / (phi * (one - s.powi(i as i32 + 1)));
 
-    let mut d = rho(search_count) * b + (1. - rho(search_count)) * a;
-    let mut yd = f(d);
+    let mut d = rho(search_count) * b + (one - rho(search_count)) * a;
+    let mut yd = f.eval(d)?;
 
     for i in 1..search_count {
         let c = if i == search_count - 1 {
-            eps * a + (1. - eps) * d
+            eps * a + (one - eps) * d
         } else {
-            rho(search_count - i + 1) * a + (1. - rho(search_count - i + 1)) * b
+            rho(search_count - i + 1) * a + (one - rho(search_count - i + 1)) * b
         };
-        let yc = f(c);
+        let yc = f.eval(c)?;
         if yc < yd {
             (b, d, yd) = (d, c, yc);
         } else {
@@ -63,27 +333,82 @@ pub fn fibonacci_search(
         }
     }
 
-    if a < b {
-        (a, b)
-    } else {
-        (b, a)
+    Ok(f.finish(d, yd, (a, b)))
+}
+
+pub fn golden_section_search<T: Float>(
+    f: &dyn UnivariateFn<T>,
+    bracket: (T, T),
+    search_count: usize,
+) -> Result<SearchResult<T>, BracketError<T>> {
+    let (mut a, mut b) = check_bracket(bracket)?;
+    let mut f = Recorder::new(f);
+    let one = T::one();
+    let phi = (one + T::from_f64(5.).sqrt()) / T::from_f64(2.);
+    let rho = phi - one;
+    let mut d = rho * b + (one - rho) * a;
+    let mut yd = f.eval(d)?;
+
+    for _ in 1..search_count {
+        let c = rho * a + (one - rho) * b;
+        let yc = f.eval(c)?;
+        if yc < yd {
+            (b, d, yd) = (d, c, yc);
+        } else {
+            (a, b) = (b, c);
+        }
     }
+
+    Ok(f.finish(d, yd, (a, b)))
 }
 
-pub fn golden_section_search(
-    f: &dyn Fn(f64) -> f64,
+/// Number of evaluations Fibonacci search needs to shrink an interval of `width` to `tol`.
+pub fn fibonacci_count(width: f64, tol: f64) -> usize {
+    // n evaluations shrink the interval by the Fibonacci number F(n + 1)
+    let (mut n, mut fib, mut fib_next) = (1, 1f64, 2f64);
+    while fib * tol < width {
+        n += 1;
+        (fib, fib_next) = (fib_next, fib + fib_next);
+    }
+    n
+}
+
+/// `fibonacci_search` with the evaluation count derived from `tol`.
+///
+/// A relative tolerance is taken at the point of the bracket closest to 0.
+pub fn fibonacci_search_tol(
+    f: &dyn UnivariateFn,
     bracket: (f64, f64),
-    search_count: usize,
-) -> (f64, f64) {
-    let (mut a, mut b) = bracket;
+    tol: Tolerance,
+    eps: f64,
+) -> Result<SearchResult, BracketError> {
+    let (a, b) = check_bracket(bracket)?;
+    tol.check()?;
+    let search_count = fibonacci_count(b - a, tol.width_over((a, b)));
+    fibonacci_search(f, (a, b), search_count, eps)
+}
+
+/// `golden_section_search` running until the bracket is within `tol` of the best point,
+/// or stops shrinking in floating point.
+pub fn golden_section_search_tol(
+    f: &dyn UnivariateFn,
+    bracket: (f64, f64),
+    tol: Tolerance,
+) -> Result<SearchResult, BracketError> {
+    let (mut a, mut b) = check_bracket(bracket)?;
+    tol.check()?;
+    let mut f = Recorder::new(f);
     let phi = (1. + 5f64.sqrt()) / 2.;
     let rho = phi - 1.;
     let mut d = rho * b + (1. - rho) * a;
-    let mut yd = f(d);
+    let mut yd = f.eval(d)?;
 
-    for _ in 1..search_count {
+    while (b - a).abs() > tol.width(d) {
         let c = rho * a + (1. - rho) * b;
-        let yc = f(c);
+        if c == a || c == b || c == d {
+            break;
+        }
+        let yc = f.eval(c)?;
         if yc < yd {
             (b, d, yd) = (d, c, yc);
         } else {
@@ -91,41 +416,397 @@ pub fn golden_section_search(
         }
     }
 
-    if a < b {
-        (a, b)
-    } else {
-        (b, a)
+    Ok(f.finish(d, yd, (a, b)))
+}
+
+/// Brent's method, parabolic interpolation safeguarded by golden section steps.
+///
+/// Stops once the argmin is located within about `tol * max(|x|, 1)`, or fails with
+/// `NotConverged` after `max_iter` iterations.
+pub fn brent_minimize(
+    f: &dyn UnivariateFn,
+    bracket: (f64, f64),
+    tol: f64,
+    max_iter: usize,
+) -> Result<SearchResult, BracketError> {
+    check_positive("tol", tol)?;
+    brent(f, bracket, |x| tol * x.abs().max(1.), max_iter)
+}
+
+/// `brent_minimize` running until the bracket is within `tol` of the best point, failing
+/// with `NotConverged` after `max_iter` iterations.
+pub fn brent_minimize_tol(
+    f: &dyn UnivariateFn,
+    bracket: (f64, f64),
+    tol: Tolerance,
+    max_iter: usize,
+) -> Result<SearchResult, BracketError> {
+    tol.check()?;
+    // the bracket shrinks to four times the step tolerance
+    brent(
+        f,
+        bracket,
+        |x| (tol.width(x) / 4.).max(f64::EPSILON * x.abs()),
+        max_iter,
+    )
+}
+
+/// Brent's method with the minimal step `step_tol(x)` around the best point `x`.
+fn brent(
+    f: &dyn UnivariateFn,
+    bracket: (f64, f64),
+    step_tol: impl Fn(f64) -> f64,
+    max_iter: usize,
+) -> Result<SearchResult, BracketError> {
+    let (mut a, mut b) = check_bracket(bracket)?;
+    let mut f = Recorder::new(f);
+
+    let c = (3. - 5f64.sqrt()) / 2.;
+    let mut x = a + c * (b - a);
+    let mut fx = f.eval(x)?;
+    let (mut w, mut fw, mut v, mut fv) = (x, fx, x, fx);
+    // d is the last step and e the one before it
+    let (mut d, mut e) = (0f64, 0f64);
+
+    for _ in 0..max_iter {
+        let m = 0.5 * (a + b);
+        let tol1 = step_tol(x);
+        let tol2 = 2. * tol1;
+        if (x - m).abs() <= tol2 - 0.5 * (b - a) {
+            return Ok(f.finish(x, fx, (a, b)));
+        }
+
+        let mut golden = true;
+        if e.abs() > tol1 {
+            // parabola through (x, fx), (w, fw), (v, fv)
+            let r = (x - w) * (fx - fv);
+            let mut q = (x - v) * (fx - fw);
+            let mut p = (x - v) * q - (x - w) * r;
+            q = 2. * (q - r);
+            if q > 0. {
+                p = -p;
+            } else {
+                q = -q;
+            }
+            let e_prev = e;
+            e = d;
+
+            // accept if it moves less than half the step before last and stays inside
+            if p.abs() < (0.5 * q * e_prev).abs() && p > q * (a - x) && p < q * (b - x) {
+                d = p / q;
+                let u = x + d;
+                if u - a < tol2 || b - u < tol2 {
+                    d = tol1.copysign(m - x);
+                }
+                golden = false;
+            }
+        }
+        if golden {
+            e = if x >= m { a - x } else { b - x };
+            d = c * e;
+        }
+
+        let u = if d.abs() >= tol1 {
+            x + d
+        } else {
+            x + tol1.copysign(d)
+        };
+        let fu = f.eval(u)?;
+
+        if fu <= fx {
+            if u >= x {
+                a = x;
+            } else {
+                b = x;
+            }
+            (v, fv, w, fw, x, fx) = (w, fw, x, fx, u, fu);
+        } else {
+            if u < x {
+                a = u;
+            } else {
+                b = u;
+            }
+            if fu <= fw || w == x {
+                (v, fv, w, fw) = (w, fw, u, fu);
+            } else if fu <= fv || v == x || v == w {
+                (v, fv) = (u, fu);
+            }
+        }
     }
+
+    Err(BracketError::NotConverged {
+        iterations: max_iter,
+        x,
+        fx,
+    })
+}
+
+/// Refines a three point bracket `a < b < c` with `f(b)` below `f(a)` and `f(c)`
+/// by repeatedly moving to the minimum of the interpolating parabola.
+///
+/// The result keeps the middle point as `x` and the outer two as the bracket.
+pub fn quadratic_fit_search(
+    f: &dyn UnivariateFn,
+    bracket: (f64, f64, f64),
+    search_count: usize,
+) -> Result<SearchResult, BracketError> {
+    let (mut a, mut b, mut c) = bracket;
+    if !(a < b && b < c && a.is_finite() && c.is_finite()) {
+        return Err(BracketError::InvalidBracket(a, c));
+    }
+    let mut f = Recorder::new(f);
+    let (mut ya, mut yb, mut yc) = (f.eval(a)?, f.eval(b)?, f.eval(c)?);
+
+    for _ in 3..search_count {
+        let denominator = ya * (b - c) + yb * (c - a) + yc * (a - b);
+        let x = 0.5 * (ya * (b * b - c * c) + yb * (c * c - a * a) + yc * (a * a - b * b))
+            / denominator;
+        if !x.is_finite() || x == b || x <= a || x >= c {
+            break;
+        }
+
+        let yx = f.eval(x)?;
+        if x > b {
+            if yx > yb {
+                (c, yc) = (x, yx);
+            } else {
+                (a, ya, b, yb) = (b, yb, x, yx);
+            }
+        } else if yx > yb {
+            (a, ya) = (x, yx);
+        } else {
+            (c, yc, b, yb) = (b, yb, x, yx);
+        }
+    }
+
+    Ok(f.finish(b, yb, (a, c)))
+}
+
+/// Shubert-Piyavskii global minimization of `f` on `[a, b]`, given a Lipschitz
+/// constant `l` of `f` on the interval.
+///
+/// Stops once the sawtooth lower bound is within `eps` of the best evaluated point,
+/// or fails with `NotConverged` after `max_evaluations` evaluations. Evaluations that
+/// are further apart than `l` allows fail with `LipschitzViolation`.
+/// Also returns the intervals where the lower bound is still below it, merged when
+/// closer than `delta`; the global minimum is certified to lie in one of them and
+/// the bracket of the result spans them all.
+pub fn shubert_piyavskii(
+    f: &dyn UnivariateFn,
+    bracket: (f64, f64),
+    l: f64,
+    eps: f64,
+    delta: f64,
+    max_evaluations: usize,
+) -> Result<(SearchResult, Vec<(f64, f64)>), BracketError> {
+    let (a, b) = check_bracket(bracket)?;
+    check_positive("l", l)?;
+    check_positive("eps", eps)?;
+    let mut f = Recorder::new(f);
+
+    // intersection of the downward cone of slope l from p and the upward one into q,
+    // it lies between them unless f changes faster than l
+    let intersection = |p: (f64, f64), q: (f64, f64)| {
+        let roundoff = f64::EPSILON * (p.1.abs() + q.1.abs() + l * (p.0.abs() + q.0.abs()));
+        if (p.1 - q.1).abs() > l * (q.0 - p.0) + roundoff {
+            return Err(BracketError::LipschitzViolation { a: p, b: q });
+        }
+        let t = ((p.1 - q.1) - l * (p.0 - q.0)) / (2. * l);
+        Ok(((p.0 + t).max(p.0).min(q.0), p.1 - t * l))
+    };
+
+    let m = (a + b) / 2.;
+    let (pa, pm, pb) = ((a, f.eval(a)?), (m, f.eval(m)?), (b, f.eval(b)?));
+    // evaluated points at even indices, lower bound minima between them at odd ones
+    let mut points = vec![pa, intersection(pa, pm)?, pm, intersection(pm, pb)?, pb];
+
+    let mut gap = f64::INFINITY;
+    while gap > eps {
+        if f.history.len() >= max_evaluations {
+            let (x, fx) = points
+                .iter()
+                .step_by(2)
+                .copied()
+                .min_by(|p, q| p.1.total_cmp(&q.1))
+                .unwrap();
+            return Err(BracketError::NotConverged {
+                iterations: f.history.len(),
+                x,
+                fx,
+            });
+        }
+        let i = (1..points.len())
+            .step_by(2)
+            .min_by(|&i, &j| points[i].1.total_cmp(&points[j].1))
+            .unwrap();
+        let p = (points[i].0, f.eval(points[i].0)?);
+        gap = p.1 - points[i].1;
+
+        let p_prev = intersection(points[i - 1], p)?;
+        let p_next = intersection(p, points[i + 1])?;
+        points.splice(i..=i, [p_prev, p, p_next]);
+    }
+
+    let p_min = points
+        .iter()
+        .step_by(2)
+        .copied()
+        .min_by(|p, q| p.1.total_cmp(&q.1))
+        .unwrap();
+
+    let mut intervals: Vec<(f64, f64)> = vec![];
+    for p in points.iter().skip(1).step_by(2) {
+        if p.1 < p_min.1 {
+            let dy = p_min.1 - p.1;
+            let (lo, hi) = ((p.0 - dy / l).max(a), (p.0 + dy / l).min(b));
+            match intervals.last_mut() {
+                Some(last) if last.1 + delta >= lo => last.1 = hi,
+                _ => intervals.push((lo, hi)),
+            }
+        }
+    }
+
+    let bracket = match (intervals.first(), intervals.last()) {
+        (Some(first), Some(last)) => (first.0.min(p_min.0), last.1.max(p_min.0)),
+        _ => (p_min.0, p_min.0),
+    };
+    Ok((f.finish(p_min.0, p_min.1, bracket), intervals))
+}
+
+/// Bisection on the derivative `df`, which has to change sign over `bracket`.
+///
+/// The result brackets a stationary point within `eps`, or as close as floating point
+/// allows. Its `x` is the bracket end with the smaller derivative magnitude and `fx`
+/// the derivative there; the history holds the evaluations of `df`. Roots of a
+/// function itself are found by `root_finding::bisection`.
+pub fn derivative_bisection(
+    df: &dyn UnivariateFn,
+    bracket: (f64, f64),
+    eps: f64,
+    max_iter: usize,
+) -> Result<SearchResult, BracketError> {
+    let (mut a, mut b) = check_bracket(bracket)?;
+    check_positive("eps", eps)?;
+    let mut df = Recorder::new(df);
+    let (mut ya, mut yb) = (df.eval(a)?, df.eval(b)?);
+    if ya * yb > 0. {
+        return Err(BracketError::NoSignChange { fa: ya, fb: yb });
+    }
+
+    if ya == 0. {
+        (b, yb) = (a, ya);
+    }
+    if yb == 0. {
+        (a, ya) = (b, yb);
+    }
+
+    let smaller = |(a, ya): (f64, f64), (b, yb): (f64, f64)| {
+        if ya.abs() <= yb.abs() {
+            (a, ya)
+        } else {
+            (b, yb)
+        }
+    };
+
+    let mut iterations = 0;
+    loop {
+        let x = a + (b - a) / 2.;
+        if b - a <= eps || x == a || x == b {
+            break;
+        }
+        if iterations == max_iter {
+            let (x, fx) = smaller((a, ya), (b, yb));
+            return Err(BracketError::NotConverged { iterations, x, fx });
+        }
+        iterations += 1;
+
+        let y = df.eval(x)?;
+        if y == 0. {
+            (a, ya, b, yb) = (x, y, x, y);
+        } else if y.signum() == ya.signum() {
+            (a, ya) = (x, y);
+        } else {
+            (b, yb) = (x, y);
+        }
+    }
+
+    let (x, y) = smaller((a, ya), (b, yb));
+    Ok(df.finish(x, y, (a, b)))
+}
+
+/// `derivative_bisection` with the derivative of a compute graph function from `Node::auto_grad`.
+pub fn derivative_bisection_graph(
+    f: &dyn Fn(Node) -> Node,
+    bracket: (f64, f64),
+    eps: f64,
+    max_iter: usize,
+) -> Result<SearchResult, BracketError> {
+    let df = |x: f64| {
+        let y = f(Tape::new().var(x));
+        Node::auto_grad(Rc::new(y))
+            .first()
+            .map_or(0., |pair| pair.grad())
+    };
+    derivative_bisection(&df, bracket, eps, max_iter)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_math_funcs::{rosenbrock, rosenbrock_grad};
 
     #[test]
     fn test_bracket_minimum_0() {
         let res = bracket_minimum(&|x| 3. * x * x - x + 5., -4., 1e-2, 2., 100);
-        assert!(res.is_some());
-        let bracket = res.unwrap();
+        assert!(res.is_ok());
+        let res = res.unwrap();
         let min_x = 1. / 6.;
-        assert!(bracket.0 < min_x);
-        assert!(bracket.1 > min_x);
+        assert!(res.bracket.0 < min_x);
+        assert!(res.bracket.1 > min_x);
+        assert!(res.bracket.0 < res.x && res.x < res.bracket.1);
+        assert_eq!(res.evaluations, res.history.len());
+        assert!(res.history.iter().all(|&(_, fx)| fx >= res.fx));
     }
 
     #[test]
     fn test_bracket_minimum_1() {
-        let res = bracket_minimum(&|x| (-x).exp(), -4., 1e-2, 2., 100);
-        assert!(res.is_none());
+        let res = bracket_minimum(&|x: f64| (-x).exp(), -4., 1e-2, 2., 100);
+        assert!(
+            matches!(res, Err(BracketError::MonotoneDecrease { .. })),
+            "{res:?}"
+        );
+    }
+
+    #[test]
+    fn test_bracket_minimum_errors() {
+        let f = |x: f64| 3. * x * x - x + 5.;
+        assert_eq!(
+            bracket_minimum(&f, -4., 1e-2, 1., 100),
+            Err(BracketError::InvalidExpansion(1.))
+        );
+        assert_eq!(
+            bracket_minimum(&f, -4., -1e-2, 2., 100),
+            Err(BracketError::InvalidStep(-1e-2))
+        );
+        assert!(matches!(
+            bracket_minimum(&f, -4., 1e-2, 2., 3),
+            Err(BracketError::IterationLimit { iterations: 3, .. })
+        ));
+        let err = bracket_minimum(&|x: f64| x.ln(), 1., 1., 2., 100).unwrap_err();
+        assert!(matches!(err, BracketError::NonFinite { x, .. } if x <= 0.));
+        assert_eq!(err.to_string(), "non-finite value -inf at x = 0");
     }
 
     #[test]
     fn test_fabonacci_search_0() {
         let f = |x| 3. * x * x - x + 5.;
         let res = bracket_minimum(&f, -4., 1e-2, 2., 100);
-        assert!(res.is_some());
-        let bracket = res.unwrap();
+        assert!(res.is_ok());
+        let bracket = res.unwrap().bracket;
         println!("initial bracket is {:?}", bracket);
-        let bracket = fibonacci_search(&f, bracket, 24, 0.01);
+        let res = fibonacci_search(&f, bracket, 24, 0.01).unwrap();
+        assert_eq!(res.evaluations, 24);
+        let bracket = res.bracket;
         let min_x = 1. / 6.;
         assert!(bracket.0 <= min_x);
         assert!(
@@ -145,10 +826,13 @@ mod tests {
     fn test_golden_section_search_0() {
         let f = |x| 3. * x * x - x + 5.;
         let res = bracket_minimum(&f, -4., 1e-2, 2., 100);
-        assert!(res.is_some());
-        let bracket = res.unwrap();
+        assert!(res.is_ok());
+        let bracket = res.unwrap().bracket;
         println!("initial bracket is {:?}", bracket);
-        let bracket = golden_section_search(&f, bracket, 24);
+        let res = golden_section_search(&f, bracket, 24).unwrap();
+        assert_eq!(res.evaluations, 24);
+        assert!(res.bracket.0 <= res.x && res.x <= res.bracket.1);
+        let bracket = res.bracket;
         let min_x = 1. / 6.;
         assert!(bracket.0 <= min_x);
         assert!(
@@ -163,4 +847,289 @@ mod tests {
             bracket.1 - min_x
         );
     }
+
+    #[test]
+    fn test_search_errors() {
+        let f = |x: f64| 3. * x * x - x + 5.;
+        assert_eq!(
+            golden_section_search(&f, (1., 1.), 10),
+            Err(BracketError::InvalidBracket(1., 1.))
+        );
+        assert!(matches!(
+            fibonacci_search(&f, (f64::NAN, 1.), 10, 0.01),
+            Err(BracketError::InvalidBracket(..))
+        ));
+        assert_eq!(
+            brent_minimize(&f, (-1., 1.), 0., 100),
+            Err(BracketError::InvalidParameter {
+                name: "tol",
+                value: 0.
+            })
+        );
+        assert!(matches!(
+            golden_section_search(&|x: f64| x.sqrt(), (-2., 1.), 10),
+            Err(BracketError::NonFinite { .. })
+        ));
+        assert_eq!(
+            derivative_bisection(&|x| 6. * x - 1., (1., 2.), 1e-10, 100),
+            Err(BracketError::NoSignChange { fa: 5., fb: 11. })
+        );
+    }
+
+    #[test]
+    fn test_fibonacci_search_tol() {
+        let f = |x: f64| x.exp() - 2. * x;
+        for width in [1., 3., 10.] {
+            for tol in [1e-2, 1e-4, 1e-6] {
+                let n = fibonacci_count(width, tol);
+                let res =
+                    fibonacci_search_tol(&f, (0., width), Tolerance::Absolute(tol), 1e-3).unwrap();
+                assert_eq!(res.evaluations, n);
+                let (a, b) = res.bracket;
+                assert!(a <= 2f64.ln() && 2f64.ln() <= b, "{width} {tol}: {a} {b}");
+                assert!(b - a <= tol * 1.002, "{width} {tol}: {}", b - a);
+            }
+        }
+        assert_eq!(fibonacci_count(1., 1.), 1);
+        assert_eq!(fibonacci_count(8., 1.), 5);
+    }
+
+    #[test]
+    fn test_golden_section_search_tol() {
+        let f = |x: f64| (x - 1e4) * (x - 1e4);
+        let res = golden_section_search_tol(&f, (0., 2e4), Tolerance::Relative(1e-8)).unwrap();
+        let (a, b) = res.bracket;
+        assert!(a <= 1e4 && 1e4 <= b);
+        assert!(b - a <= 1e-4);
+
+        // an absolute tolerance below the spacing of floats stops when the bracket does
+        let res = golden_section_search_tol(&f, (0., 2e4), Tolerance::Absolute(1e-20)).unwrap();
+        assert!((res.x - 1e4).abs() < 1e-6);
+        assert_eq!(
+            golden_section_search_tol(&f, (0., 2e4), Tolerance::Relative(-1.)),
+            Err(BracketError::InvalidParameter {
+                name: "tol",
+                value: -1.
+            })
+        );
+    }
+
+    #[test]
+    fn test_bracket_minimum_bounded() {
+        let f = |x: f64| (-x).exp();
+        let res = bracket_minimum_bounded(&f, 0., 1e-2, 2., (-1., 3.), 100).unwrap();
+        assert_eq!(res.x, 3.);
+        assert_eq!(res.bracket.1, 3.);
+        assert!(res.history.iter().all(|&(x, _)| (-1. ..=3.).contains(&x)));
+
+        // starting at a bound the search turns inwards, or stops if it is the minimum
+        let res = bracket_minimum_bounded(&f, 3., 1e-2, 2., (-1., 3.), 100).unwrap();
+        assert_eq!((res.x, res.bracket.1), (3., 3.));
+        let res = bracket_minimum_bounded(&|x: f64| -f(x), 3., 1e-2, 2., (-1., 3.), 100).unwrap();
+        assert_eq!((res.x, res.bracket.0), (-1., -1.));
+
+        // an interior minimum gives the same bracket as without bounds
+        let f = |x: f64| 3. * x * x - x + 5.;
+        assert_eq!(
+            bracket_minimum_bounded(&f, -4., 1e-2, 2., (-10., 10.), 100),
+            bracket_minimum(&f, -4., 1e-2, 2., 100)
+        );
+        assert_eq!(
+            bracket_minimum_bounded(&f, 0., 1e-2, 2., (1., 1.), 100),
+            Err(BracketError::InvalidBracket(1., 1.))
+        );
+    }
+
+    #[test]
+    fn test_generic_floats() {
+        let f = |x: f32| 3. * x * x - x + 5.;
+        let bracket = bracket_minimum(&f, -4., 1e-2, 2., 100).unwrap().bracket;
+        let res = golden_section_search(&f, bracket, 40).unwrap();
+        assert!((res.x - 1. / 6.).abs() < 1e-3);
+        let res = fibonacci_search(&f, bracket, 40, 0.01).unwrap();
+        assert!((res.x - 1. / 6.).abs() < 1e-3);
+
+        // the minimizer of (x - p)^2 moves with p, and so does a bracket built around p
+        use crate::autograd::dual::Dual;
+        let p = Dual::var(2.);
+        let f = |x: Dual| (x - p) * (x - p);
+        let bracket = bracket_minimum(&f, p - 1., Dual::from(1e-2), Dual::from(2.), 100)
+            .unwrap()
+            .bracket;
+        let res = golden_section_search(&f, bracket, 60).unwrap();
+        assert!((res.x.value() - 2.).abs() < 1e-6);
+        assert!((res.x.derivative() - 1.).abs() < 1e-12);
+        assert!(res.fx.derivative().abs() < 1e-6);
+    }
+
+    fn golden_section_count(bracket: (f64, f64), tol: f64) -> usize {
+        let rho = (5f64.sqrt() - 1.) / 2.;
+        ((2. * tol / (bracket.1 - bracket.0)).ln() / rho.ln()).ceil() as usize + 1
+    }
+
+    #[test]
+    fn test_brent_minimize_quadratic() {
+        let f = |x| 3. * x * x - x + 5.;
+        let bracket = bracket_minimum(&f, -4., 1e-2, 2., 100).unwrap().bracket;
+        let tol = 1e-8;
+        let res = brent_minimize(&f, bracket, tol, 100).unwrap();
+        let min_x = 1. / 6.;
+
+        assert!((res.x - min_x).abs() < tol, "x - min_x = {}", res.x - min_x);
+        assert!((res.fx - f(min_x)).abs() < 1e-12);
+
+        let golden_count = golden_section_count(bracket, tol);
+        let golden = golden_section_search(&f, bracket, golden_count)
+            .unwrap()
+            .bracket;
+        assert!(golden.0 <= min_x && min_x <= golden.1);
+        assert!(
+            res.evaluations * 3 < golden_count,
+            "brent: {} evaluations, golden section: {golden_count}",
+            res.evaluations
+        );
+
+        assert!(matches!(
+            brent_minimize(&f, bracket, tol, 2),
+            Err(BracketError::NotConverged { iterations: 2, .. })
+        ));
+        assert!(matches!(
+            brent_minimize_tol(&f, bracket, Tolerance::Absolute(tol), 2),
+            Err(BracketError::NotConverged { iterations: 2, .. })
+        ));
+    }
+
+    #[test]
+    fn test_brent_minimize_rosenbrock_line() {
+        let (a, b) = (1., 100.);
+        let tol = 1e-8;
+
+        for p in [[-1.4, 0.9], [-1.4, 1.], [-1.4, 1.1], [-2., -1.]] {
+            let g = rosenbrock_grad(&p, a, b);
+            let norm = g[0].hypot(g[1]);
+            let d = [-g[0] / norm, -g[1] / norm];
+            let f = |alpha: f64| rosenbrock(&[p[0] + alpha * d[0], p[1] + alpha * d[1]], a, b);
+
+            let bracket = bracket_minimum(&f, 0., 1e-8, 2., 100).unwrap().bracket;
+            let res = brent_minimize(&f, bracket, tol, 100).unwrap();
+            let (x, fx) = (res.x, res.fx);
+            assert!(bracket.0 < x && x < bracket.1);
+            assert!(fx <= f(x - 1e-6) && fx <= f(x + 1e-6));
+
+            let golden_count = golden_section_count(bracket, tol);
+            let golden = golden_section_search(&f, bracket, golden_count)
+                .unwrap()
+                .bracket;
+            let golden_x = (golden.0 + golden.1) / 2.;
+            assert!((golden_x - x).abs() < 1e-6, "golden {golden_x}, brent {x}");
+            assert!(
+                res.evaluations < golden_count,
+                "brent: {} evaluations, golden section: {golden_count}",
+                res.evaluations
+            );
+        }
+    }
+
+    #[test]
+    fn test_quadratic_fit_search() {
+        let f = |x| 3. * x * x - x + 5.;
+        let res = quadratic_fit_search(&f, (-1., 0.5, 2.), 4).unwrap();
+        let ((a, c), b) = (res.bracket, res.x);
+        assert!(a < b && b < c);
+        assert!((b - 1. / 6.).abs() < 1e-12);
+        assert_eq!(res.evaluations, 4);
+
+        let f = |x: f64| x.exp() - 2. * x;
+        let res = quadratic_fit_search(&f, (0., 1., 2.), 12).unwrap();
+        let ((a, c), b) = (res.bracket, res.x);
+        assert!(a <= 2f64.ln() && 2f64.ln() <= c);
+        assert!(
+            (b - 2f64.ln()).abs() < 1e-5,
+            "b - ln(2) = {}",
+            b - 2f64.ln()
+        );
+
+        assert_eq!(
+            quadratic_fit_search(&f, (0., 2., 1.), 12),
+            Err(BracketError::InvalidBracket(0., 1.))
+        );
+    }
+
+    #[test]
+    fn test_shubert_piyavskii() {
+        // multimodal on [2.7, 7.5] with global minimum -1.899599 at 5.145735
+        let f = |x: f64| x.sin() + (10. * x / 3.).sin();
+        let l = 1. + 10. / 3.;
+        let (res, intervals) = shubert_piyavskii(&f, (2.7, 7.5), l, 1e-6, 1e-2, 10000).unwrap();
+
+        let min_x = 5.145735285;
+        assert!((res.x - min_x).abs() < 1e-3, "x = {}", res.x);
+        assert!((res.fx + 1.899599).abs() < 1e-6, "y = {}", res.fx);
+        assert!(!intervals.is_empty());
+        assert!(intervals.iter().any(|&(lo, hi)| lo <= min_x && min_x <= hi));
+        assert!(intervals.windows(2).all(|w| w[0].1 < w[1].0));
+        assert!(res.bracket.0 <= min_x && min_x <= res.bracket.1);
+        assert!(res.history.iter().all(|&(x, _)| (2.7..=7.5).contains(&x)));
+
+        // an eps below roundoff is never reached
+        let err = shubert_piyavskii(&f, (2.7, 7.5), l, 1e-17, 1e-2, 10000).unwrap_err();
+        match err {
+            BracketError::NotConverged { iterations, x, .. } => {
+                assert_eq!(iterations, 10000);
+                assert!((x - min_x).abs() < 1e-3, "x = {x}");
+            }
+            _ => panic!("{err}"),
+        }
+
+        // 10 sin x has Lipschitz constant 10, not 0.5
+        let f = |x: f64| 10. * x.sin();
+        assert!(matches!(
+            shubert_piyavskii(&f, (0., 6.), 0.5, 1e-6, 1e-2, 1000),
+            Err(BracketError::LipschitzViolation { .. })
+        ));
+    }
+
+    #[test]
+    fn test_derivative_bisection() {
+        let df = |x| 6. * x - 1.;
+        let (a, b) = derivative_bisection(&df, (1., -4.), 1e-10, 100)
+            .unwrap()
+            .bracket;
+        assert!(a <= 1. / 6. && 1. / 6. <= b);
+        assert!(b - a <= 1e-10);
+
+        let f = |x: Node| 3. * &x * &x - &x + 5.;
+        let bracket = derivative_bisection_graph(&f, (-4., 1.), 1e-10, 100)
+            .unwrap()
+            .bracket;
+        assert_eq!(bracket, (a, b));
+
+        let f = |x: Node| -x.sin();
+        let res = derivative_bisection_graph(&f, (1., 2.), 1e-12, 100).unwrap();
+        let (a, b) = res.bracket;
+        assert!((a - std::f64::consts::FRAC_PI_2).abs() < 1e-12);
+        assert!((b - std::f64::consts::FRAC_PI_2).abs() < 1e-12);
+        assert!(res.fx.abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_derivative_bisection_termination() {
+        // an eps below the spacing of floats stops once the bracket ends are adjacent
+        let df = |x: f64| x - 1e4 - 1e-9;
+        let res = derivative_bisection(&df, (0., 2e4), 1e-20, 100).unwrap();
+        let (a, b) = res.bracket;
+        assert!(a < b && (a + (b - a) / 2. == a || a + (b - a) / 2. == b));
+        assert!(res.evaluations < 100);
+
+        assert!(matches!(
+            derivative_bisection(&df, (0., 2e4), 1e-10, 10),
+            Err(BracketError::NotConverged { iterations: 10, .. })
+        ));
+        for eps in [0., -1., f64::NAN] {
+            assert!(matches!(
+                derivative_bisection(&df, (0., 2e4), eps, 100),
+                Err(BracketError::InvalidParameter { name: "eps", .. })
+            ));
+        }
+    }
 }
@@ -0,0 +1,252 @@
+use super::{
+    bracket_minimum, bracket_minimum_bounded, brent_minimize_tol, fibonacci_search_tol,
+    golden_section_search_tol, BracketError, SearchResult, Tolerance,
+};
+use crate::objective::UnivariateFn;
+
+/// Interval search used to refine a bracket.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LineMethod {
+    Fibonacci,
+    #[default]
+    GoldenSection,
+    Brent,
+}
+
+/// Brackets a minimum of a univariate function with `bracket_minimum` and
+/// refines it until the tolerance is met.
+///
+/// With bounds the search stays inside them and a minimum at a bound is returned
+/// as that bound, as for step lengths limited by a feasible region.
+///
+/// ```
+/// use optimize_examples::bracketing::{LineMethod, LineMinimizer, Tolerance};
+///
+/// let line = LineMinimizer::new()
+///     .with_method(LineMethod::Fibonacci)
+///     .with_tolerance(Tolerance::Absolute(1e-8));
+/// let res = line.minimize(&|x| (x - 2.) * (x - 2.), 0.).unwrap();
+/// assert!((res.x - 2.).abs() < 1e-8);
+///
+/// let res = line.with_bounds(0., 1.).minimize(&|x: f64| (-x).exp(), 0.).unwrap();
+/// assert_eq!(res.x, 1.);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LineMinimizer {
+    method: LineMethod,
+    tolerance: Tolerance,
+    step: f64,
+    expansion: f64,
+    max_expansions: usize,
+    eps: f64,
+    max_iter: usize,
+    bounds: Option<(f64, f64)>,
+}
+
+impl Default for LineMinimizer {
+    fn default() -> Self {
+        Self {
+            method: LineMethod::default(),
+            tolerance: Tolerance::Relative(1e-6),
+            step: 1e-2,
+            expansion: 2.,
+            max_expansions: 100,
+            eps: 0.01,
+            max_iter: 100,
+            bounds: None,
+        }
+    }
+}
+
+impl LineMinimizer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_method(mut self, method: LineMethod) -> Self {
+        self.method = method;
+        self
+    }
+
+    pub fn with_tolerance(mut self, tolerance: Tolerance) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+
+    /// Initial step of the bracketing phase.
+    pub fn with_step(mut self, step: f64) -> Self {
+        self.step = step;
+        self
+    }
+
+    /// Factor the bracketing step grows by.
+    pub fn with_expansion(mut self, expansion: f64) -> Self {
+        self.expansion = expansion;
+        self
+    }
+
+    pub fn with_max_expansions(mut self, max_expansions: usize) -> Self {
+        self.max_expansions = max_expansions;
+        self
+    }
+
+    /// Offset of the last Fibonacci search point.
+    pub fn with_eps(mut self, eps: f64) -> Self {
+        self.eps = eps;
+        self
+    }
+
+    /// Iteration limit of Brent's method, which fails with `NotConverged` once it is reached.
+    pub fn with_max_iter(mut self, max_iter: usize) -> Self {
+        self.max_iter = max_iter;
+        self
+    }
+
+    /// Restricts the search to `[lo, hi]`.
+    pub fn with_bounds(mut self, lo: f64, hi: f64) -> Self {
+        self.bounds = Some((lo, hi));
+        self
+    }
+
+    pub fn bounds(&self) -> Option<(f64, f64)> {
+        self.bounds
+    }
+
+    pub fn bracket(&self, f: &dyn UnivariateFn, x0: f64) -> Result<SearchResult, BracketError> {
+        match self.bounds {
+            Some(bounds) => bracket_minimum_bounded(
+                f,
+                x0,
+                self.step,
+                self.expansion,
+                bounds,
+                self.max_expansions,
+            ),
+            None => bracket_minimum(f, x0, self.step, self.expansion, self.max_expansions),
+        }
+    }
+
+    pub fn refine(
+        &self,
+        f: &dyn UnivariateFn,
+        bracket: (f64, f64),
+    ) -> Result<SearchResult, BracketError> {
+        match self.method {
+            LineMethod::Fibonacci => fibonacci_search_tol(f, bracket, self.tolerance, self.eps),
+            LineMethod::GoldenSection => golden_section_search_tol(f, bracket, self.tolerance),
+            LineMethod::Brent => brent_minimize_tol(f, bracket, self.tolerance, self.max_iter),
+        }
+    }
+
+    /// Brackets from `x0` and refines, evaluations and history cover both phases.
+    pub fn minimize(&self, f: &dyn UnivariateFn, x0: f64) -> Result<SearchResult, BracketError> {
+        let bracketed = self.bracket(f, x0)?;
+        let mut refined = self.refine(f, bracketed.bracket)?;
+
+        // a bound ending the bracket, unless the refinement found a lower interior point
+        let (x, (a, b)) = (bracketed.x, bracketed.bracket);
+        if (x == a || x == b) && bracketed.fx <= refined.fx {
+            refined.x = x;
+            refined.fx = bracketed.fx;
+            refined.bracket = (refined.bracket.0.min(x), refined.bracket.1.max(x));
+        }
+        refined.evaluations += bracketed.evaluations;
+        refined.history.splice(0..0, bracketed.history);
+        Ok(refined)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bracketing::golden_section_search;
+    use crate::test_math_funcs::{rosenbrock, rosenbrock_grad};
+
+    #[test]
+    fn minimize_to_tolerance() {
+        let f = |x: f64| 3. * x * x - x + 5.;
+        let min_x = 1. / 6.;
+
+        for method in [
+            LineMethod::Fibonacci,
+            LineMethod::GoldenSection,
+            LineMethod::Brent,
+        ] {
+            for tol in [1e-3, 1e-5, 1e-7] {
+                let line = LineMinimizer::new()
+                    .with_method(method)
+                    .with_tolerance(Tolerance::Absolute(tol));
+                let res = line.minimize(&f, -4.).unwrap();
+                let (a, b) = res.bracket;
+                assert!(
+                    a <= min_x && min_x <= b,
+                    "{method:?} {tol}: {:?}",
+                    res.bracket
+                );
+                assert!(b - a <= tol * 1.02, "{method:?} {tol}: width {}", b - a);
+                assert_eq!(res.evaluations, res.history.len());
+
+                let mut xs = res.history.iter().map(|&(x, _)| x).collect::<Vec<_>>();
+                xs.sort_by(f64::total_cmp);
+                xs.dedup();
+                assert_eq!(xs.len(), res.history.len(), "{method:?} repeated a point");
+            }
+        }
+    }
+
+    #[test]
+    fn bounded_minimize() {
+        for method in [
+            LineMethod::Fibonacci,
+            LineMethod::GoldenSection,
+            LineMethod::Brent,
+        ] {
+            let line = LineMinimizer::new()
+                .with_method(method)
+                .with_tolerance(Tolerance::Absolute(1e-6))
+                .with_bounds(0., 10.);
+
+            // monotone functions end at a bound
+            let res = line.minimize(&|x: f64| (-x).exp(), 0.).unwrap();
+            assert_eq!((res.x, res.fx), (10., (-10f64).exp()), "{method:?}");
+            assert!(res.bracket.1 == 10. && res.bracket.0 > 9.);
+            let res = line.minimize(&|x: f64| x, 4.).unwrap();
+            assert_eq!((res.x, res.bracket.0), (0., 0.), "{method:?}");
+            let res = line.minimize(&|x: f64| -x, 10.).unwrap();
+            assert_eq!(res.x, 10., "{method:?}");
+            assert!(res.history.iter().all(|&(x, _)| (0. ..=10.).contains(&x)));
+
+            // an interior minimum next to a bound the expansion reached first
+            let res = line.minimize(&|x: f64| (x - 9.5).powi(2), 0.).unwrap();
+            assert!((res.x - 9.5).abs() < 1e-6, "{method:?} {}", res.x);
+
+            // the same minimum as without bounds when it is inside them
+            let f = |x: f64| 3. * x * x - x + 5.;
+            let res = line.minimize(&f, 7.).unwrap();
+            assert!((res.x - 1. / 6.).abs() < 1e-6, "{method:?} {}", res.x);
+        }
+    }
+
+    #[test]
+    fn rosenbrock_line_step() {
+        // the line search of examples/gradient_descent_2.rs
+        let p = [-1.4, 0.9];
+        let g = rosenbrock_grad(&p, 1., 100.);
+        let norm = g[0].hypot(g[1]);
+        let d = [-g[0] / norm, -g[1] / norm];
+        let f = |alpha: f64| rosenbrock(&[p[0] + alpha * d[0], p[1] + alpha * d[1]], 1., 100.);
+
+        let line = LineMinimizer::new().with_step(1e-8);
+        let res = line.minimize(&f, 0.).unwrap();
+        assert!(res.fx <= f(res.x - 1e-5) && res.fx <= f(res.x + 1e-5));
+
+        let bracket = line.bracket(&f, 0.).unwrap().bracket;
+        let fixed = golden_section_search(&f, bracket, 3).unwrap();
+        let fixed_x = (fixed.bracket.0 + fixed.bracket.1) / 2.;
+        assert!(
+            (fixed_x - res.x).abs() > 1e-3,
+            "tolerance step {}, 3 evaluation step {fixed_x}",
+            res.x
+        );
+    }
+}
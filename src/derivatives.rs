@@ -0,0 +1,242 @@
+mod complex;
+
+pub use complex::Complex;
+
+/// Finite difference scheme for first derivatives.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Difference {
+    Forward,
+    Backward,
+    #[default]
+    Central,
+}
+
+impl Difference {
+    /// Step balancing truncation and rounding error, `sqrt(eps)` for the one-sided
+    /// schemes and `cbrt(eps)` for the central one, scaled by `max(|x|, 1)`.
+    ///
+    /// The step is adjusted so that `x + h` is exactly representable.
+    pub fn step(&self, x: f64) -> f64 {
+        let h = match self {
+            Difference::Forward | Difference::Backward => f64::EPSILON.sqrt(),
+            Difference::Central => f64::EPSILON.cbrt(),
+        } * x.abs().max(1.);
+        (x + h) - x
+    }
+
+    pub fn estimate(&self, f: &dyn Fn(f64) -> f64, x: f64, h: f64) -> f64 {
+        match self {
+            Difference::Forward => forward_difference(f, x, h),
+            Difference::Backward => backward_difference(f, x, h),
+            Difference::Central => central_difference(f, x, h),
+        }
+    }
+}
+
+pub fn forward_difference(f: &dyn Fn(f64) -> f64, x: f64, h: f64) -> f64 {
+    (f(x + h) - f(x)) / h
+}
+
+pub fn backward_difference(f: &dyn Fn(f64) -> f64, x: f64, h: f64) -> f64 {
+    (f(x) - f(x - h)) / h
+}
+
+pub fn central_difference(f: &dyn Fn(f64) -> f64, x: f64, h: f64) -> f64 {
+    (f(x + h) - f(x - h)) / (2. * h)
+}
+
+/// Derivative of `f` at `x` with the step chosen by `scheme.step`.
+pub fn derivative(f: &dyn Fn(f64) -> f64, x: f64, scheme: Difference) -> f64 {
+    scheme.estimate(f, x, scheme.step(x))
+}
+
+/// Complex-step derivative `Im(f(x + ih)) / h`, free of subtractive cancellation so
+/// the step can be tiny and the result is exact to machine precision.
+pub fn complex_step(f: &dyn Fn(Complex) -> Complex, x: f64) -> f64 {
+    let h = 1e-20 * x.abs().max(1.);
+    f(Complex::new(x, h)).im / h
+}
+
+/// Richardson extrapolation of central differences with steps `h, h/2, ..., h/2^(levels-1)`.
+///
+/// Every level cancels the next even power of `h` in the truncation error.
+pub fn richardson(f: &dyn Fn(f64) -> f64, x: f64, h: f64, levels: usize) -> f64 {
+    assert!(levels > 0);
+    assert!(h > 0.);
+
+    let mut row: Vec<f64> = vec![];
+    for i in 0..levels {
+        let mut next = vec![central_difference(f, x, h / 2f64.powi(i as i32))];
+        for k in 1..=i {
+            let factor = 4f64.powi(k as i32);
+            let estimate = next[k - 1];
+            next.push(estimate + (estimate - row[k - 1]) / (factor - 1.));
+        }
+        row = next;
+    }
+
+    row[levels - 1]
+}
+
+/// Gradient of `f: R^n -> R` at `x`, one partial derivative per coordinate.
+pub fn gradient(f: &dyn Fn(&[f64]) -> f64, x: &[f64], scheme: Difference) -> Vec<f64> {
+    (0..x.len())
+        .map(|i| {
+            let partial = |t: f64| {
+                let mut point = x.to_vec();
+                point[i] = t;
+                f(&point)
+            };
+            derivative(&partial, x[i], scheme)
+        })
+        .collect()
+}
+
+/// Gradient of `f: R^n -> R` at `x` by complex steps.
+pub fn gradient_complex_step(f: &dyn Fn(&[Complex]) -> Complex, x: &[f64]) -> Vec<f64> {
+    let mut point = x.iter().map(|&x| Complex::from(x)).collect::<Vec<_>>();
+    (0..x.len())
+        .map(|i| {
+            let h = 1e-20 * x[i].abs().max(1.);
+            point[i].im = h;
+            let partial = f(&point).im / h;
+            point[i].im = 0.;
+            partial
+        })
+        .collect()
+}
+
+/// Jacobian of `f: R^n -> R^m` at `x`, as `m` rows of `n` partial derivatives.
+pub fn jacobian(f: &dyn Fn(&[f64]) -> Vec<f64>, x: &[f64], scheme: Difference) -> Vec<Vec<f64>> {
+    let mut point = x.to_vec();
+    let mut eval = |i: usize, t: f64| {
+        point[i] = t;
+        let y = f(&point);
+        point[i] = x[i];
+        y
+    };
+
+    let y = f(x);
+    let mut jac = vec![vec![0.; x.len()]; y.len()];
+    for j in 0..x.len() {
+        let h = scheme.step(x[j]);
+        let (column, denominator) = match scheme {
+            Difference::Forward => (sub(&eval(j, x[j] + h), &y), h),
+            Difference::Backward => (sub(&y, &eval(j, x[j] - h)), h),
+            Difference::Central => (sub(&eval(j, x[j] + h), &eval(j, x[j] - h)), 2. * h),
+        };
+        for (row, d) in jac.iter_mut().zip(column) {
+            row[j] = d / denominator;
+        }
+    }
+
+    jac
+}
+
+fn sub(a: &[f64], b: &[f64]) -> Vec<f64> {
+    assert!(a.len() == b.len());
+    a.iter().zip(b).map(|(a, b)| a - b).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::{assert_abs_diff_eq, assert_relative_eq};
+
+    use super::*;
+    use crate::test_math_funcs::{rosenbrock, rosenbrock_grad};
+
+    #[test]
+    fn test_scalar_differences() {
+        let f = |x: f64| x.sin() * x.exp();
+        let df = |x: f64| (x.sin() + x.cos()) * x.exp();
+
+        for x in [-2., -0.3, 0., 0.7, 4.] {
+            assert_relative_eq!(
+                derivative(&f, x, Difference::Forward),
+                df(x),
+                epsilon = 1e-7,
+                max_relative = 1e-6
+            );
+            assert_relative_eq!(
+                derivative(&f, x, Difference::Backward),
+                df(x),
+                epsilon = 1e-7,
+                max_relative = 1e-6
+            );
+            assert_relative_eq!(
+                derivative(&f, x, Difference::Central),
+                df(x),
+                epsilon = 1e-9,
+                max_relative = 1e-9
+            );
+        }
+    }
+
+    #[test]
+    fn test_complex_step() {
+        let f = |x: Complex| x.sin() * x.exp() / (1. + x * x).sqrt() + x.powi(-2).ln();
+        let df = |x: f64| {
+            let g = x.sin() * x.exp() / (1. + x * x).sqrt();
+            g * (x.cos() / x.sin() + 1. - x / (1. + x * x)) - 2. / x
+        };
+
+        for x in [-2., -0.3, 0.7, 4.] {
+            assert_relative_eq!(complex_step(&f, x), df(x), max_relative = 1e-14);
+        }
+    }
+
+    #[test]
+    fn test_richardson() {
+        let f = |x: f64| (2. * x).sin();
+        let x: f64 = 0.4;
+        let h = 0.1;
+        let exact = 2. * (2. * x).cos();
+
+        let plain = (central_difference(&f, x, h) - exact).abs();
+        let extrapolated = (richardson(&f, x, h, 4) - exact).abs();
+        assert!(extrapolated < 1e-10, "extrapolated error = {extrapolated}");
+        assert!(extrapolated < plain * 1e-6);
+    }
+
+    #[test]
+    fn test_gradient_rosenbrock() {
+        let (a, b) = (1., 100.);
+        let f = |x: &[f64]| rosenbrock(&[x[0], x[1]], a, b);
+        let f_complex = |x: &[Complex]| {
+            (a - x[0]) * (a - x[0]) + b * (x[1] - x[0] * x[0]) * (x[1] - x[0] * x[0])
+        };
+
+        for x in [[-1.4, 0.9], [0.3, -0.2], [2., 3.]] {
+            let expected = rosenbrock_grad(&x, a, b);
+            let central = gradient(&f, &x, Difference::Central);
+            let forward = gradient(&f, &x, Difference::Forward);
+            let complex = gradient_complex_step(&f_complex, &x);
+            for i in 0..2 {
+                assert_abs_diff_eq!(central[i], expected[i], epsilon = 1e-5);
+                assert_abs_diff_eq!(forward[i], expected[i], epsilon = 1e-3);
+                assert_abs_diff_eq!(complex[i], expected[i], epsilon = 1e-10);
+            }
+        }
+    }
+
+    #[test]
+    fn test_jacobian() {
+        let f = |x: &[f64]| vec![x[0] * x[1], x[0].sin() + x[2], x[1].exp()];
+        let x: [f64; 3] = [0.5, -1.5, 2.];
+        let expected = [[x[1], x[0], 0.], [x[0].cos(), 0., 1.], [0., x[1].exp(), 0.]];
+
+        for scheme in [
+            Difference::Forward,
+            Difference::Backward,
+            Difference::Central,
+        ] {
+            let jac = jacobian(&f, &x, scheme);
+            assert_eq!(jac.len(), 3);
+            for i in 0..3 {
+                for j in 0..3 {
+                    assert_abs_diff_eq!(jac[i][j], expected[i][j], epsilon = 1e-6);
+                }
+            }
+        }
+    }
+}
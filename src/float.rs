@@ -0,0 +1,114 @@
+use std::fmt::{Debug, Display};
+use std::ops::{Add, Div, Mul, Neg, Sub};
+
+/// Real number type the bracketing searches and compute graphs are generic over.
+///
+/// Implemented for `f64`, `f32` and `Dual`, other types (extended precision,
+/// interval arithmetic) only need to provide these operations.
+pub trait Float:
+    Copy
+    + Debug
+    + Display
+    + PartialOrd
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<Output = Self>
+    + Div<Output = Self>
+    + Neg<Output = Self>
+    + 'static
+{
+    fn from_f64(x: f64) -> Self;
+    /// Nearest `f64`, for reporting and tolerances.
+    fn to_f64(self) -> f64;
+
+    fn zero() -> Self {
+        Self::from_f64(0.0)
+    }
+
+    fn one() -> Self {
+        Self::from_f64(1.0)
+    }
+
+    /// Machine epsilon.
+    fn epsilon() -> Self;
+
+    fn infinity() -> Self {
+        Self::from_f64(f64::INFINITY)
+    }
+
+    fn is_finite(self) -> bool;
+    fn is_nan(self) -> bool;
+
+    fn abs(self) -> Self;
+    fn signum(self) -> Self;
+    fn max(self, other: Self) -> Self;
+    fn min(self, other: Self) -> Self;
+    fn sqrt(self) -> Self;
+    fn exp(self) -> Self;
+    fn ln(self) -> Self;
+    fn ln_1p(self) -> Self;
+    fn exp_m1(self) -> Self;
+    fn powi(self, n: i32) -> Self;
+    fn powf(self, p: Self) -> Self;
+    fn sin(self) -> Self;
+    fn cos(self) -> Self;
+    fn tan(self) -> Self;
+    fn atan(self) -> Self;
+    /// Four quadrant arctangent of `self` (y) and `x`.
+    fn atan2(self, x: Self) -> Self;
+    fn sinh(self) -> Self;
+    fn cosh(self) -> Self;
+    fn tanh(self) -> Self;
+    fn hypot(self, other: Self) -> Self;
+}
+
+macro_rules! impl_float {
+    ($t:ty, $($method:ident),*; $($binary:ident),*) => {
+        impl Float for $t {
+            fn from_f64(x: f64) -> Self {
+                x as $t
+            }
+
+            fn to_f64(self) -> f64 {
+                self as f64
+            }
+
+            fn epsilon() -> Self {
+                <$t>::EPSILON
+            }
+
+            fn is_finite(self) -> bool {
+                <$t>::is_finite(self)
+            }
+
+            fn is_nan(self) -> bool {
+                <$t>::is_nan(self)
+            }
+
+            fn powi(self, n: i32) -> Self {
+                <$t>::powi(self, n)
+            }
+
+            $(
+                fn $method(self) -> Self {
+                    <$t>::$method(self)
+                }
+            )*
+
+            $(
+                fn $binary(self, other: Self) -> Self {
+                    <$t>::$binary(self, other)
+                }
+            )*
+        }
+    };
+}
+
+impl_float!(
+    f64, abs, signum, sqrt, exp, ln, ln_1p, exp_m1, sin, cos, tan, atan, sinh, cosh, tanh;
+    max, min, powf, atan2, hypot
+);
+impl_float!(
+    f32, abs, signum, sqrt, exp, ln, ln_1p, exp_m1, sin, cos, tan, atan, sinh, cosh, tanh;
+    max, min, powf, atan2, hypot
+);
@@ -1,3 +1,9 @@
 pub mod autograd;
 pub mod bracketing;
+pub mod derivatives;
+pub mod float;
+pub mod line_search;
+pub mod objective;
+pub mod optimizer;
+pub mod root_finding;
 pub mod test_math_funcs;
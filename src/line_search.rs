@@ -0,0 +1,455 @@
+mod hager_zhang;
+mod strong_wolfe;
+
+pub use hager_zhang::HagerZhang;
+pub use strong_wolfe::StrongWolfe;
+
+use std::fmt::Display;
+
+use crate::objective::{LineFunction, Objective};
+
+/// `phi(alpha) = f(x + alpha d)` and its slope `phi'(alpha) = grad f(x + alpha d) . d`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LinePoint {
+    pub alpha: f64,
+    pub value: f64,
+    pub slope: f64,
+}
+
+/// Step conditions that hold at an accepted point.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Conditions {
+    /// Sufficient decrease, `phi(a) <= phi(0) + c1 a phi'(0)`.
+    pub armijo: bool,
+    /// `phi'(a) >= c2 phi'(0)`, with `armijo` the weak Wolfe conditions.
+    pub curvature: bool,
+    /// `|phi'(a)| <= c2 |phi'(0)|`, with `armijo` the strong Wolfe conditions.
+    pub strong_curvature: bool,
+    /// Hager-Zhang approximate Wolfe conditions,
+    /// `(2 c1 - 1) phi'(0) >= phi'(a) >= c2 phi'(0)` and `phi(a) <= phi(0) + epsilon |phi(0)|`.
+    pub approximate_wolfe: bool,
+}
+
+impl Conditions {
+    pub fn check(p0: &LinePoint, p: &LinePoint, c1: f64, c2: f64, epsilon: f64) -> Self {
+        let curvature = p.slope >= c2 * p0.slope;
+        Self {
+            armijo: p.value <= p0.value + c1 * p.alpha * p0.slope,
+            curvature,
+            strong_curvature: p.slope.abs() <= -c2 * p0.slope,
+            approximate_wolfe: curvature
+                && (2. * c1 - 1.) * p0.slope >= p.slope
+                && p.value <= p0.value + epsilon * p0.value.abs(),
+        }
+    }
+
+    pub fn wolfe(&self) -> bool {
+        self.armijo && self.curvature
+    }
+
+    pub fn strong_wolfe(&self) -> bool {
+        self.armijo && self.strong_curvature
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LineSearchResult {
+    pub alpha: f64,
+    /// `x + alpha d`
+    pub x: Vec<f64>,
+    pub fx: f64,
+    pub grad: Vec<f64>,
+    pub conditions: Conditions,
+    /// Evaluations of the objective along the line, not counting the start.
+    pub evaluations: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LineSearchError {
+    /// `d` is not a descent direction at `x`.
+    NotDescent {
+        slope: f64,
+    },
+    /// The objective or its slope is not finite at the start.
+    NonFinite {
+        value: f64,
+        slope: f64,
+    },
+    /// No acceptable step within the evaluation limit, `best` is the lowest point seen.
+    MaxIterations {
+        best: LinePoint,
+    },
+    InvalidParameter {
+        name: &'static str,
+        value: f64,
+    },
+}
+
+impl Display for LineSearchError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NotDescent { slope } => write!(f, "not a descent direction, slope {slope}"),
+            Self::NonFinite { value, slope } => {
+                write!(f, "non-finite start, value {value}, slope {slope}")
+            }
+            Self::MaxIterations { best } => write!(
+                f,
+                "no acceptable step, best f({}) = {}",
+                best.alpha, best.value
+            ),
+            Self::InvalidParameter { name, value } => write!(f, "invalid {name} = {value}"),
+        }
+    }
+}
+
+impl std::error::Error for LineSearchError {}
+
+/// Step length selection along a direction.
+pub trait LineSearch {
+    /// Searches for a step along `line`, starting at `alpha = 0`.
+    fn search_line(&self, line: &LineFunction) -> Result<LineSearchResult, LineSearchError>;
+
+    /// Searches along `d` from `x`, given `fx` and `grad` at `x`.
+    fn search_from(
+        &self,
+        objective: &dyn Objective,
+        x: &[f64],
+        fx: f64,
+        grad: &[f64],
+        d: &[f64],
+    ) -> Result<LineSearchResult, LineSearchError> {
+        self.search_line(&LineFunction::new(objective, x, d).with_start(fx, grad))
+    }
+
+    fn search(
+        &self,
+        objective: &dyn Objective,
+        x: &[f64],
+        d: &[f64],
+    ) -> Result<LineSearchResult, LineSearchError> {
+        self.search_line(&LineFunction::new(objective, x, d))
+    }
+}
+
+/// Default `epsilon` of the approximate Wolfe conditions.
+const APPROXIMATE_EPSILON: f64 = 1e-6;
+
+fn dot(a: &[f64], b: &[f64]) -> f64 {
+    a.iter().zip(b).map(|(a, b)| a * b).sum()
+}
+
+fn check_parameter(
+    name: &'static str,
+    value: f64,
+    lo: f64,
+    hi: f64,
+) -> Result<(), LineSearchError> {
+    if lo < value && value < hi {
+        Ok(())
+    } else {
+        Err(LineSearchError::InvalidParameter { name, value })
+    }
+}
+
+/// Search state along a `LineFunction`.
+///
+/// Non-finite values are reported as `+inf` with slope `+inf`, so searches treat
+/// them as overshooting.
+struct Line<'a, 'b> {
+    f: &'a LineFunction<'b>,
+    p0: LinePoint,
+    best: LinePoint,
+    /// Points asked for, cached or not.
+    iterations: usize,
+    /// Objective evaluations made by `f` before the search.
+    start_evaluations: usize,
+}
+
+impl<'a, 'b> Line<'a, 'b> {
+    fn new(f: &'a LineFunction<'b>) -> Result<Self, LineSearchError> {
+        let (value, grad) = f.value_and_gradient(0.);
+        let p0 = LinePoint {
+            alpha: 0.,
+            value,
+            slope: dot(&grad, f.direction()),
+        };
+        if !p0.value.is_finite() || !p0.slope.is_finite() {
+            return Err(LineSearchError::NonFinite {
+                value: p0.value,
+                slope: p0.slope,
+            });
+        }
+        if p0.slope >= 0. {
+            return Err(LineSearchError::NotDescent { slope: p0.slope });
+        }
+        Ok(Self {
+            f,
+            p0,
+            best: p0,
+            iterations: 0,
+            start_evaluations: f.evaluations(),
+        })
+    }
+
+    fn record(&mut self, p: LinePoint) -> LinePoint {
+        self.iterations += 1;
+        let p = if p.value.is_finite() && !p.slope.is_nan() {
+            p
+        } else {
+            LinePoint {
+                value: f64::INFINITY,
+                slope: f64::INFINITY,
+                ..p
+            }
+        };
+        if p.value < self.best.value {
+            self.best = p;
+        }
+        p
+    }
+
+    /// Value only, the slope is left NaN.
+    fn value(&mut self, alpha: f64) -> LinePoint {
+        let value = self.f.value(alpha);
+        let p = self.record(LinePoint {
+            alpha,
+            value,
+            slope: 0.,
+        });
+        LinePoint {
+            slope: f64::NAN,
+            ..p
+        }
+    }
+
+    fn eval(&mut self, alpha: f64) -> LinePoint {
+        let (value, grad) = self.f.value_and_gradient(alpha);
+        let slope = dot(&grad, self.f.direction());
+        self.record(LinePoint {
+            alpha,
+            value,
+            slope,
+        })
+    }
+
+    fn max_iterations(&self) -> LineSearchError {
+        LineSearchError::MaxIterations { best: self.best }
+    }
+
+    fn finish(self, p: LinePoint, c1: f64, c2: f64, epsilon: f64) -> LineSearchResult {
+        let grad = self.f.gradient(p.alpha);
+        let p = LinePoint {
+            slope: dot(&grad, self.f.direction()),
+            ..p
+        };
+        LineSearchResult {
+            alpha: p.alpha,
+            x: self.f.point(p.alpha),
+            fx: p.value,
+            grad,
+            conditions: Conditions::check(&self.p0, &p, c1, c2, epsilon),
+            evaluations: self.f.evaluations() - self.start_evaluations,
+        }
+    }
+}
+
+/// Backtracking until the Armijo sufficient decrease condition holds.
+///
+/// Only values are evaluated while backtracking, the gradient once at the accepted step.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Backtracking {
+    pub alpha0: f64,
+    /// Factor the step shrinks by.
+    pub rho: f64,
+    pub c1: f64,
+    /// Only used to report the curvature conditions.
+    pub c2: f64,
+    pub max_iter: usize,
+}
+
+impl Default for Backtracking {
+    fn default() -> Self {
+        Self {
+            alpha0: 1.,
+            rho: 0.5,
+            c1: 1e-4,
+            c2: 0.9,
+            max_iter: 50,
+        }
+    }
+}
+
+impl LineSearch for Backtracking {
+    fn search_line(&self, line: &LineFunction) -> Result<LineSearchResult, LineSearchError> {
+        check_parameter("alpha0", self.alpha0, 0., f64::INFINITY)?;
+        check_parameter("rho", self.rho, 0., 1.)?;
+        check_parameter("c1", self.c1, 0., 1.)?;
+        let mut line = Line::new(line)?;
+        let p0 = line.p0;
+
+        let mut alpha = self.alpha0;
+        for _ in 0..self.max_iter {
+            let p = line.value(alpha);
+            if p.value <= p0.value + self.c1 * alpha * p0.slope {
+                return Ok(line.finish(p, self.c1, self.c2, APPROXIMATE_EPSILON));
+            }
+            alpha *= self.rho;
+        }
+        Err(line.max_iterations())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::autograd::compute_graph::node::Node;
+    use crate::bracketing::LineMinimizer;
+    use crate::objective::{FnObjective, GraphObjective};
+    use crate::test_math_funcs::rosenbrock_objective;
+
+    const START_POINTS: [[f64; 2]; 3] = [[-1.4, 0.9], [-1.4, 1.], [-1.4, 1.1]];
+
+    fn graph_rosenbrock(a: f64, b: f64) -> GraphObjective {
+        GraphObjective::new(2, move |x: &[Node]| {
+            (a - &x[0]).powi(2) + b * (&x[1] - x[0].powi(2)).powi(2)
+        })
+    }
+
+    fn steepest_descent(objective: &dyn Objective, x: &[f64]) -> Vec<f64> {
+        let mut grad = vec![0.; x.len()];
+        objective.gradient(x, &mut grad);
+        grad.iter().map(|g| -g).collect()
+    }
+
+    fn searches() -> Vec<(&'static str, Box<dyn LineSearch>)> {
+        vec![
+            ("backtracking", Box::new(Backtracking::default())),
+            ("strong wolfe", Box::new(StrongWolfe::default())),
+            ("hager zhang", Box::new(HagerZhang::default())),
+        ]
+    }
+
+    #[test]
+    fn conditions_hold_on_rosenbrock() {
+        let objective = rosenbrock_objective(1., 100.);
+        for x in START_POINTS {
+            let d = steepest_descent(&objective, &x);
+            for (name, search) in searches() {
+                let res = search.search(&objective, &x, &d).unwrap();
+                assert!(res.conditions.armijo, "{name} at {x:?}: {res:?}");
+                assert!(res.fx < objective.value(&x), "{name}");
+                assert_eq!(res.fx, objective.value(&res.x), "{name}");
+                match name {
+                    "strong wolfe" => {
+                        assert!(res.conditions.strong_wolfe(), "{name} at {x:?}: {res:?}")
+                    }
+                    "hager zhang" => assert!(
+                        res.conditions.wolfe() || res.conditions.approximate_wolfe,
+                        "{name} at {x:?}: {res:?}"
+                    ),
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn graph_objective_matches_closures() {
+        let closures = rosenbrock_objective(1., 100.);
+        let graph = graph_rosenbrock(1., 100.);
+        for x in START_POINTS {
+            let d = steepest_descent(&closures, &x);
+            for (name, search) in searches() {
+                let a = search.search(&closures, &x, &d).unwrap();
+                let b = search.search(&graph, &x, &d).unwrap();
+                assert!((a.alpha - b.alpha).abs() < 1e-12, "{name}");
+                assert_eq!(a.conditions, b.conditions, "{name}");
+            }
+        }
+    }
+
+    #[test]
+    fn searches_share_the_line_cache() {
+        let objective = rosenbrock_objective(1., 100.);
+        let x = [-1.4, 1.];
+        let d = steepest_descent(&objective, &x);
+        for (name, search) in searches() {
+            let line = LineFunction::new(&objective, &x, &d);
+            let res = search.search_line(&line).unwrap();
+            assert_eq!(res.evaluations + 1, line.evaluations(), "{name}");
+            assert_eq!(line.value(res.alpha), res.fx, "{name}");
+            assert_eq!(line.point(res.alpha), res.x, "{name}");
+
+            // a second search on the same line is answered from the cache
+            let again = search.search_line(&line).unwrap();
+            assert_eq!(again.alpha, res.alpha, "{name}");
+            assert_eq!(line.evaluations(), res.evaluations + 1, "{name}");
+        }
+    }
+
+    #[test]
+    fn fewer_evaluations_than_exact_search() {
+        let objective = rosenbrock_objective(1., 100.);
+        let line = LineMinimizer::new().with_step(1e-8);
+        for x in START_POINTS {
+            let d = steepest_descent(&objective, &x);
+            let phi = |alpha: f64| objective.value(&[x[0] + alpha * d[0], x[1] + alpha * d[1]]);
+            let exact = line.minimize(&phi, 0.).unwrap();
+            for (name, search) in searches() {
+                let res = search.search(&objective, &x, &d).unwrap();
+                assert!(
+                    res.evaluations * 2 < exact.evaluations,
+                    "{name}: {}, exact: {}",
+                    res.evaluations,
+                    exact.evaluations
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn overshooting_into_undefined_region() {
+        // defined for x > 0 only, a unit step lands at -1
+        let objective = FnObjective::new(1, |x| x[0] - 2. * x[0].ln())
+            .with_gradient(|x, grad| grad[0] = 1. - 2. / x[0]);
+        for (name, search) in searches() {
+            let res = search.search(&objective, &[1.], &[-2.]);
+            assert!(
+                matches!(res, Err(LineSearchError::NotDescent { .. })),
+                "{name}"
+            );
+
+            let res = search.search(&objective, &[1.], &[4.]).unwrap();
+            assert!(res.x[0] > 0. && res.fx < 1., "{name}: {res:?}");
+            let res = search.search(&objective, &[4.], &[-8.]).unwrap();
+            assert!(res.x[0] > 0. && res.fx.is_finite(), "{name}: {res:?}");
+        }
+    }
+
+    #[test]
+    fn invalid_parameters() {
+        let objective = rosenbrock_objective(1., 100.);
+        let x = [-1.4, 0.9];
+        let d = steepest_descent(&objective, &x);
+        let res = StrongWolfe {
+            c1: 0.5,
+            c2: 0.1,
+            ..Default::default()
+        }
+        .search(&objective, &x, &d);
+        assert_eq!(
+            res,
+            Err(LineSearchError::InvalidParameter {
+                name: "c2",
+                value: 0.1
+            })
+        );
+        let res = Backtracking {
+            max_iter: 2,
+            alpha0: 10.,
+            ..Default::default()
+        }
+        .search(&objective, &x, &d);
+        assert!(matches!(res, Err(LineSearchError::MaxIterations { .. })));
+    }
+}
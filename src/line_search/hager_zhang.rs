@@ -0,0 +1,199 @@
+use std::ops::ControlFlow;
+
+use super::{
+    check_parameter, Conditions, Line, LinePoint, LineSearch, LineSearchError, LineSearchResult,
+};
+use crate::objective::LineFunction;
+
+/// Hager-Zhang line search of CG_DESCENT, accepting steps that satisfy either the
+/// Wolfe or the approximate Wolfe conditions.
+///
+/// The approximate conditions stay meaningful near a minimum, where differences of
+/// `f` drown in rounding errors and only the slope can be trusted.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HagerZhang {
+    pub alpha0: f64,
+    /// Sufficient decrease parameter, `c1` of the Wolfe conditions.
+    pub delta: f64,
+    /// Curvature parameter, `c2` of the Wolfe conditions.
+    pub sigma: f64,
+    /// Allowed relative increase of `f` under the approximate Wolfe conditions.
+    pub epsilon: f64,
+    /// Bisection point of the update step.
+    pub theta: f64,
+    /// Secant steps have to shrink the interval below this factor to skip bisection.
+    pub gamma: f64,
+    /// Growth of the initial bracketing step.
+    pub rho: f64,
+    /// Limit on evaluations of the objective.
+    pub max_iter: usize,
+}
+
+impl Default for HagerZhang {
+    fn default() -> Self {
+        Self {
+            alpha0: 1.,
+            delta: 0.1,
+            sigma: 0.9,
+            epsilon: 1e-6,
+            theta: 0.5,
+            gamma: 0.66,
+            rho: 5.,
+            max_iter: 50,
+        }
+    }
+}
+
+/// `Break` ends the search early, with an accepted point or an error.
+type Step<T> = ControlFlow<Result<LinePoint, LineSearchError>, T>;
+
+struct Search<'a, 'b> {
+    params: &'a HagerZhang,
+    line: Line<'a, 'b>,
+    /// `phi(0) + epsilon |phi(0)|`
+    f_max: f64,
+}
+
+impl Search<'_, '_> {
+    fn eval(&mut self, alpha: f64) -> Step<LinePoint> {
+        if self.line.iterations >= self.params.max_iter {
+            return ControlFlow::Break(Err(self.line.max_iterations()));
+        }
+        let p = self.line.eval(alpha);
+        let c = Conditions::check(
+            &self.line.p0,
+            &p,
+            self.params.delta,
+            self.params.sigma,
+            self.params.epsilon,
+        );
+        if c.wolfe() || c.approximate_wolfe {
+            ControlFlow::Break(Ok(p))
+        } else {
+            ControlFlow::Continue(p)
+        }
+    }
+
+    /// Initial bracket `[a, b]` with `phi'(a) < 0`, `phi(a) <= f_max` and `phi'(b) >= 0`.
+    fn bracket(&mut self) -> Step<(LinePoint, LinePoint)> {
+        let mut a = self.line.p0;
+        let mut c = self.eval(self.params.alpha0)?;
+        loop {
+            if c.slope >= 0. {
+                return ControlFlow::Continue((a, c));
+            }
+            if c.value > self.f_max {
+                return self.bisect(self.line.p0, c);
+            }
+            a = c;
+            c = self.eval(self.params.rho * c.alpha)?;
+        }
+    }
+
+    /// Shrinks `[a, b]` where `phi(b) > f_max` until the slope turns non-negative.
+    fn bisect(&mut self, mut a: LinePoint, mut b: LinePoint) -> Step<(LinePoint, LinePoint)> {
+        loop {
+            let d = self.eval((1. - self.params.theta) * a.alpha + self.params.theta * b.alpha)?;
+            if d.slope >= 0. {
+                return ControlFlow::Continue((a, d));
+            }
+            if d.value <= self.f_max {
+                a = d;
+            } else {
+                b = d;
+            }
+        }
+    }
+
+    fn update(&mut self, a: LinePoint, b: LinePoint, c: LinePoint) -> Step<(LinePoint, LinePoint)> {
+        if !(a.alpha < c.alpha && c.alpha < b.alpha) {
+            ControlFlow::Continue((a, b))
+        } else if c.slope >= 0. {
+            ControlFlow::Continue((a, c))
+        } else if c.value <= self.f_max {
+            ControlFlow::Continue((c, b))
+        } else {
+            self.bisect(a, c)
+        }
+    }
+
+    fn update_at(
+        &mut self,
+        a: LinePoint,
+        b: LinePoint,
+        alpha: f64,
+    ) -> Step<(LinePoint, LinePoint)> {
+        if a.alpha < alpha && alpha < b.alpha {
+            let c = self.eval(alpha)?;
+            self.update(a, b, c)
+        } else {
+            ControlFlow::Continue((a, b))
+        }
+    }
+
+    fn secant2(&mut self, a: LinePoint, b: LinePoint) -> Step<(LinePoint, LinePoint)> {
+        let alpha = secant(&a, &b);
+        let (new_a, new_b) = self.update_at(a, b, alpha)?;
+        if new_b.alpha == alpha {
+            self.update_at(new_a, new_b, secant(&b, &new_b))
+        } else if new_a.alpha == alpha {
+            self.update_at(new_a, new_b, secant(&a, &new_a))
+        } else {
+            ControlFlow::Continue((new_a, new_b))
+        }
+    }
+
+    /// Alternates secant and bisection steps on the initial bracket, `Continue` once the
+    /// interval stops shrinking in floating point.
+    fn run(&mut self) -> Step<()> {
+        let (mut a, mut b) = self.bracket()?;
+        loop {
+            let width = b.alpha - a.alpha;
+            let (mut new_a, mut new_b) = self.secant2(a, b)?;
+            if new_b.alpha - new_a.alpha > self.params.gamma * width {
+                (new_a, new_b) = self.update_at(new_a, new_b, (new_a.alpha + new_b.alpha) / 2.)?;
+            }
+            if new_b.alpha - new_a.alpha >= width {
+                return ControlFlow::Continue(());
+            }
+            (a, b) = (new_a, new_b);
+        }
+    }
+}
+
+/// Zero of the linear interpolation of the slopes at `a` and `b`.
+fn secant(a: &LinePoint, b: &LinePoint) -> f64 {
+    let alpha = (a.alpha * b.slope - b.alpha * a.slope) / (b.slope - a.slope);
+    if alpha.is_finite() {
+        alpha
+    } else {
+        (a.alpha + b.alpha) / 2.
+    }
+}
+
+impl LineSearch for HagerZhang {
+    fn search_line(&self, line: &LineFunction) -> Result<LineSearchResult, LineSearchError> {
+        check_parameter("alpha0", self.alpha0, 0., f64::INFINITY)?;
+        check_parameter("delta", self.delta, 0., 0.5)?;
+        check_parameter("sigma", self.sigma, self.delta, 1.)?;
+        check_parameter("epsilon", self.epsilon, 0., f64::INFINITY)?;
+        check_parameter("theta", self.theta, 0., 1.)?;
+        check_parameter("gamma", self.gamma, 0., 1.)?;
+        check_parameter("rho", self.rho, 1., f64::INFINITY)?;
+
+        let line = Line::new(line)?;
+        let f_max = line.p0.value + self.epsilon * line.p0.value.abs();
+        let mut search = Search {
+            params: self,
+            line,
+            f_max,
+        };
+        match search.run() {
+            ControlFlow::Break(Ok(p)) => {
+                Ok(search.line.finish(p, self.delta, self.sigma, self.epsilon))
+            }
+            ControlFlow::Break(Err(e)) => Err(e),
+            ControlFlow::Continue(()) => Err(search.line.max_iterations()),
+        }
+    }
+}
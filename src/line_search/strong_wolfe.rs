@@ -0,0 +1,107 @@
+use super::{
+    check_parameter, Line, LinePoint, LineSearch, LineSearchError, LineSearchResult,
+    APPROXIMATE_EPSILON,
+};
+use crate::objective::LineFunction;
+
+/// Expanding search for a strong Wolfe step, refined by `zoom` with safeguarded
+/// cubic interpolation (Nocedal & Wright, algorithms 3.5 and 3.6).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StrongWolfe {
+    pub alpha0: f64,
+    pub alpha_max: f64,
+    pub c1: f64,
+    pub c2: f64,
+    /// Limit on evaluations of the objective.
+    pub max_iter: usize,
+}
+
+impl Default for StrongWolfe {
+    fn default() -> Self {
+        Self {
+            alpha0: 1.,
+            alpha_max: 1e10,
+            c1: 1e-4,
+            c2: 0.9,
+            max_iter: 50,
+        }
+    }
+}
+
+/// Minimizer of the cubic matching values and slopes at `lo` and `hi`, or the
+/// midpoint when it is undefined or too close to an end.
+fn cubic_step(lo: &LinePoint, hi: &LinePoint) -> f64 {
+    let d1 = lo.slope + hi.slope - 3. * (lo.value - hi.value) / (lo.alpha - hi.alpha);
+    let d2 = (hi.alpha - lo.alpha).signum() * (d1 * d1 - lo.slope * hi.slope).sqrt();
+    let alpha =
+        hi.alpha - (hi.alpha - lo.alpha) * (hi.slope + d2 - d1) / (hi.slope - lo.slope + 2. * d2);
+
+    let (a, b) = (lo.alpha.min(hi.alpha), lo.alpha.max(hi.alpha));
+    let margin = 0.1 * (b - a);
+    if alpha.is_finite() && a + margin <= alpha && alpha <= b - margin {
+        alpha
+    } else {
+        (a + b) / 2.
+    }
+}
+
+impl StrongWolfe {
+    fn sufficient_decrease(&self, p0: &LinePoint, p: &LinePoint) -> bool {
+        p.value <= p0.value + self.c1 * p.alpha * p0.slope
+    }
+
+    fn zoom(
+        &self,
+        mut line: Line<'_, '_>,
+        mut lo: LinePoint,
+        mut hi: LinePoint,
+    ) -> Result<LineSearchResult, LineSearchError> {
+        let p0 = line.p0;
+        while line.iterations < self.max_iter {
+            let p = line.eval(cubic_step(&lo, &hi));
+            if !self.sufficient_decrease(&p0, &p) || p.value >= lo.value {
+                hi = p;
+            } else {
+                if p.slope.abs() <= -self.c2 * p0.slope {
+                    return Ok(line.finish(p, self.c1, self.c2, APPROXIMATE_EPSILON));
+                }
+                if p.slope * (hi.alpha - lo.alpha) >= 0. {
+                    hi = lo;
+                }
+                lo = p;
+            }
+        }
+        Err(line.max_iterations())
+    }
+}
+
+impl LineSearch for StrongWolfe {
+    fn search_line(&self, line: &LineFunction) -> Result<LineSearchResult, LineSearchError> {
+        check_parameter("alpha0", self.alpha0, 0., self.alpha_max)?;
+        check_parameter("c1", self.c1, 0., 1.)?;
+        check_parameter("c2", self.c2, self.c1, 1.)?;
+        let mut line = Line::new(line)?;
+        let p0 = line.p0;
+
+        let mut prev = p0;
+        let mut alpha = self.alpha0;
+        while line.iterations < self.max_iter {
+            let p = line.eval(alpha);
+            if !self.sufficient_decrease(&p0, &p) || (prev.alpha > 0. && p.value >= prev.value) {
+                return self.zoom(line, prev, p);
+            }
+            if p.slope.abs() <= -self.c2 * p0.slope {
+                return Ok(line.finish(p, self.c1, self.c2, APPROXIMATE_EPSILON));
+            }
+            if p.slope >= 0. {
+                return self.zoom(line, p, prev);
+            }
+            if alpha >= self.alpha_max {
+                break;
+            }
+            prev = p;
+            alpha = (2. * alpha).min(self.alpha_max);
+        }
+        Err(line.max_iterations())
+    }
+}
@@ -0,0 +1,554 @@
+mod line_function;
+
+pub use line_function::LineFunction;
+
+use std::cell::Cell;
+
+use crate::autograd::compute_graph::{
+    node::{Node, NodeGradPair},
+    tape::Tape,
+};
+use crate::derivatives::{self, Difference};
+use crate::float::Float;
+
+/// Number of times each part of an objective was evaluated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Evaluations {
+    pub value: usize,
+    pub gradient: usize,
+    pub hessian: usize,
+}
+
+/// Interior-mutable evaluation counter for `&self` objectives.
+#[derive(Debug, Default)]
+pub struct EvalCounter {
+    value: Cell<usize>,
+    gradient: Cell<usize>,
+    hessian: Cell<usize>,
+}
+
+impl EvalCounter {
+    pub fn count_value(&self) {
+        self.value.set(self.value.get() + 1);
+    }
+
+    pub fn count_gradient(&self) {
+        self.gradient.set(self.gradient.get() + 1);
+    }
+
+    pub fn count_hessian(&self) {
+        self.hessian.set(self.hessian.get() + 1);
+    }
+
+    pub fn get(&self) -> Evaluations {
+        Evaluations {
+            value: self.value.get(),
+            gradient: self.gradient.get(),
+            hessian: self.hessian.get(),
+        }
+    }
+
+    pub fn reset(&self) {
+        self.value.set(0);
+        self.gradient.set(0);
+        self.hessian.set(0);
+    }
+}
+
+/// Function `R^n -> R` to be minimized.
+///
+/// Only `dim` and `value` are required, the gradient and Hessian fall back to
+/// central finite differences.
+pub trait Objective {
+    fn dim(&self) -> usize;
+
+    fn value(&self, x: &[f64]) -> f64;
+
+    fn gradient(&self, x: &[f64], grad: &mut [f64]) {
+        assert!(x.len() == self.dim() && grad.len() == self.dim());
+        grad.copy_from_slice(&derivatives::gradient(
+            &|x| self.value(x),
+            x,
+            Difference::Central,
+        ));
+    }
+
+    fn value_and_gradient(&self, x: &[f64], grad: &mut [f64]) -> f64 {
+        self.gradient(x, grad);
+        self.value(x)
+    }
+
+    /// Row-major `dim x dim` Hessian, central differences of `gradient` unless overridden.
+    fn hessian(&self, x: &[f64], hess: &mut [f64]) {
+        gradient_jacobian(self, x, hess);
+    }
+
+    fn evaluations(&self) -> Evaluations {
+        Evaluations::default()
+    }
+}
+
+/// Function `R -> R`, as searched by `bracketing`.
+///
+/// Implemented for closures `Fn(T) -> T` and for `LineFunction`.
+pub trait UnivariateFn<T: Float = f64> {
+    fn value(&self, x: T) -> T;
+
+    /// Central difference with the step of `Difference::Central` unless overridden.
+    fn derivative(&self, x: T) -> T {
+        let h = T::epsilon().powf(T::from_f64(1. / 3.)) * x.abs().max(T::one());
+        let h = (x + h) - x;
+        (self.value(x + h) - self.value(x - h)) / (h + h)
+    }
+
+    /// Both at once, for functions that get the derivative along with the value.
+    fn value_and_derivative(&self, x: T) -> (T, T) {
+        (self.value(x), self.derivative(x))
+    }
+}
+
+impl<T: Float, F: Fn(T) -> T> UnivariateFn<T> for F {
+    fn value(&self, x: T) -> T {
+        self(x)
+    }
+}
+
+/// Symmetrized central-difference Jacobian of the gradient.
+fn gradient_jacobian<O: Objective + ?Sized>(objective: &O, x: &[f64], hess: &mut [f64]) {
+    let n = objective.dim();
+    assert!(x.len() == n && hess.len() == n * n);
+    let jac = derivatives::jacobian(
+        &|x| {
+            let mut grad = vec![0.; n];
+            objective.gradient(x, &mut grad);
+            grad
+        },
+        x,
+        Difference::Central,
+    );
+    for i in 0..n {
+        for j in 0..n {
+            hess[i * n + j] = (jac[i][j] + jac[j][i]) / 2.;
+        }
+    }
+}
+
+impl<O: Objective + ?Sized> Objective for &O {
+    fn dim(&self) -> usize {
+        (**self).dim()
+    }
+
+    fn value(&self, x: &[f64]) -> f64 {
+        (**self).value(x)
+    }
+
+    fn gradient(&self, x: &[f64], grad: &mut [f64]) {
+        (**self).gradient(x, grad)
+    }
+
+    fn value_and_gradient(&self, x: &[f64], grad: &mut [f64]) -> f64 {
+        (**self).value_and_gradient(x, grad)
+    }
+
+    fn hessian(&self, x: &[f64], hess: &mut [f64]) {
+        (**self).hessian(x, hess)
+    }
+
+    fn evaluations(&self) -> Evaluations {
+        (**self).evaluations()
+    }
+}
+
+type ValueFn = Box<dyn Fn(&[f64]) -> f64>;
+type GradientFn = Box<dyn Fn(&[f64], &mut [f64])>;
+
+/// Objective from plain closures.
+pub struct FnObjective {
+    dim: usize,
+    value: ValueFn,
+    gradient: Option<GradientFn>,
+    hessian: Option<GradientFn>,
+    counter: EvalCounter,
+}
+
+impl FnObjective {
+    pub fn new(dim: usize, value: impl Fn(&[f64]) -> f64 + 'static) -> Self {
+        Self {
+            dim,
+            value: Box::new(value),
+            gradient: None,
+            hessian: None,
+            counter: EvalCounter::default(),
+        }
+    }
+
+    pub fn with_gradient(mut self, gradient: impl Fn(&[f64], &mut [f64]) + 'static) -> Self {
+        self.gradient = Some(Box::new(gradient));
+        self
+    }
+
+    /// Hessian closure writing a row-major `dim x dim` matrix.
+    pub fn with_hessian(mut self, hessian: impl Fn(&[f64], &mut [f64]) + 'static) -> Self {
+        self.hessian = Some(Box::new(hessian));
+        self
+    }
+
+    pub fn reset_evaluations(&self) {
+        self.counter.reset();
+    }
+}
+
+impl Objective for FnObjective {
+    fn dim(&self) -> usize {
+        self.dim
+    }
+
+    fn value(&self, x: &[f64]) -> f64 {
+        self.counter.count_value();
+        (self.value)(x)
+    }
+
+    fn gradient(&self, x: &[f64], grad: &mut [f64]) {
+        self.counter.count_gradient();
+        match &self.gradient {
+            Some(gradient) => gradient(x, grad),
+            None => grad.copy_from_slice(&derivatives::gradient(
+                &|x| self.value(x),
+                x,
+                Difference::Central,
+            )),
+        }
+    }
+
+    fn hessian(&self, x: &[f64], hess: &mut [f64]) {
+        self.counter.count_hessian();
+        match (&self.hessian, &self.gradient) {
+            (Some(hessian), _) => hessian(x, hess),
+            (None, Some(_)) => gradient_jacobian(self, x, hess),
+            (None, None) => FiniteDiff::new(self).hessian(x, hess),
+        }
+    }
+
+    fn evaluations(&self) -> Evaluations {
+        self.counter.get()
+    }
+}
+
+type GraphFn = Box<dyn Fn(&[Node]) -> Node>;
+
+/// Objective built as a compute graph, differentiated with `Node::auto_grad` and
+/// `Node::hessian`.
+///
+/// Every evaluation records the graph on a fresh tape.
+pub struct GraphObjective {
+    dim: usize,
+    func: GraphFn,
+    counter: EvalCounter,
+}
+
+impl GraphObjective {
+    /// `func` is recorded on a fresh tape, so it can not use nodes from `Node::start`,
+    /// which live on the current tape and panic when combined with the variables.
+    /// Constants are written as scalar literals, `&x[0] * 2.`, or recorded with
+    /// `x[0].tape().var(c)`.
+    pub fn new(dim: usize, func: impl Fn(&[Node]) -> Node + 'static) -> Self {
+        Self {
+            dim,
+            func: Box::new(func),
+            counter: EvalCounter::default(),
+        }
+    }
+
+    fn record(&self, x: &[f64]) -> (Vec<Node>, Node) {
+        assert!(x.len() == self.dim);
+        let tape = Tape::new();
+        let vars = x.iter().map(|&x| tape.var(x)).collect::<Vec<_>>();
+        let y = (self.func)(&vars);
+        (vars, y)
+    }
+
+    fn record_gradient(&self, x: &[f64], grad: &mut [f64]) -> f64 {
+        let (vars, y) = self.record(x);
+
+        let pairs = Node::auto_grad(std::rc::Rc::new(y.clone()));
+        grad.copy_from_slice(&NodeGradPair::gradient(&vars, &pairs));
+        y.value()
+    }
+
+    pub fn reset_evaluations(&self) {
+        self.counter.reset();
+    }
+}
+
+impl Objective for GraphObjective {
+    fn dim(&self) -> usize {
+        self.dim
+    }
+
+    fn value(&self, x: &[f64]) -> f64 {
+        self.counter.count_value();
+        self.record(x).1.value()
+    }
+
+    fn gradient(&self, x: &[f64], grad: &mut [f64]) {
+        self.counter.count_gradient();
+        self.record_gradient(x, grad);
+    }
+
+    fn value_and_gradient(&self, x: &[f64], grad: &mut [f64]) -> f64 {
+        self.counter.count_value();
+        self.counter.count_gradient();
+        self.record_gradient(x, grad)
+    }
+
+    fn hessian(&self, x: &[f64], hess: &mut [f64]) {
+        self.counter.count_hessian();
+        let (vars, y) = self.record(x);
+        let n = self.dim;
+
+        let Some((starts, reduced)) = Node::hessian_with_starts(&y) else {
+            return gradient_jacobian(self, x, hess);
+        };
+        // the variables come first on the tape, later starts are constants of `func`
+        let first = vars.first().map_or(0, |v| v.id());
+        let positions = starts
+            .iter()
+            .map(|node| Some(node.id() - first).filter(|&i| i < n))
+            .collect::<Vec<_>>();
+
+        hess.fill(0.);
+        for (row, i) in reduced.iter().zip(&positions) {
+            for (h, j) in row.iter().zip(&positions) {
+                if let (Some(i), Some(j)) = (i, j) {
+                    hess[i * n + j] = *h;
+                }
+            }
+        }
+    }
+
+    fn evaluations(&self) -> Evaluations {
+        self.counter.get()
+    }
+}
+
+/// Replaces the derivatives of an objective with finite differences of its values.
+pub struct FiniteDiff<O> {
+    inner: O,
+    scheme: Difference,
+    counter: EvalCounter,
+}
+
+impl<O: Objective> FiniteDiff<O> {
+    pub fn new(inner: O) -> Self {
+        Self {
+            inner,
+            scheme: Difference::Central,
+            counter: EvalCounter::default(),
+        }
+    }
+
+    pub fn with_scheme(mut self, scheme: Difference) -> Self {
+        self.scheme = scheme;
+        self
+    }
+
+    pub fn inner(&self) -> &O {
+        &self.inner
+    }
+}
+
+impl<O: Objective> Objective for FiniteDiff<O> {
+    fn dim(&self) -> usize {
+        self.inner.dim()
+    }
+
+    fn value(&self, x: &[f64]) -> f64 {
+        self.inner.value(x)
+    }
+
+    fn gradient(&self, x: &[f64], grad: &mut [f64]) {
+        self.counter.count_gradient();
+        grad.copy_from_slice(&derivatives::gradient(
+            &|x| self.inner.value(x),
+            x,
+            self.scheme,
+        ));
+    }
+
+    /// Second differences of the values, `(f(x + h_i + h_j) - f(x + h_i - h_j)
+    /// - f(x - h_i + h_j) + f(x - h_i - h_j)) / (4 h_i h_j)`.
+    fn hessian(&self, x: &[f64], hess: &mut [f64]) {
+        self.counter.count_hessian();
+        let n = self.dim();
+        assert!(x.len() == n && hess.len() == n * n);
+
+        let steps = x
+            .iter()
+            .map(|&x| (x + 1e-4 * x.abs().max(1.)) - x)
+            .collect::<Vec<_>>();
+        let mut point = x.to_vec();
+        let mut eval = |i: usize, si: f64, j: usize, sj: f64| {
+            point[i] += si * steps[i];
+            point[j] += sj * steps[j];
+            let y = self.inner.value(&point);
+            point[i] = x[i];
+            point[j] = x[j];
+            y
+        };
+
+        for i in 0..n {
+            for j in i..n {
+                let h = (eval(i, 1., j, 1.) - eval(i, 1., j, -1.) - eval(i, -1., j, 1.)
+                    + eval(i, -1., j, -1.))
+                    / (4. * steps[i] * steps[j]);
+                hess[i * n + j] = h;
+                hess[j * n + i] = h;
+            }
+        }
+    }
+
+    fn evaluations(&self) -> Evaluations {
+        Evaluations {
+            value: self.inner.evaluations().value,
+            ..self.counter.get()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_abs_diff_eq;
+
+    use super::*;
+    use crate::test_math_funcs::{rosenbrock_grad, rosenbrock_hessian, rosenbrock_objective};
+
+    fn graph_rosenbrock(a: f64, b: f64) -> GraphObjective {
+        GraphObjective::new(2, move |x| {
+            let t = &x[1] - &x[0] * &x[0];
+            (a - &x[0]) * (a - &x[0]) + b * &t * &t
+        })
+    }
+
+    #[test]
+    fn test_objectives_agree_on_rosenbrock() {
+        let (a, b) = (1., 100.);
+        let analytic = rosenbrock_objective(a, b);
+        let graph = graph_rosenbrock(a, b);
+        let numeric = FiniteDiff::new(FnObjective::new(2, move |x| {
+            crate::test_math_funcs::rosenbrock(&[x[0], x[1]], a, b)
+        }));
+        let objectives: [&dyn Objective; 3] = [&analytic, &graph, &numeric];
+
+        for x in [[-1.4, 0.9], [0.3, -0.2], [1., 1.]] {
+            let expected_grad = rosenbrock_grad(&x, a, b);
+            let expected_hess = rosenbrock_hessian(&x, a, b);
+            for (k, objective) in objectives.iter().enumerate() {
+                let tol = if k == 2 { 1e-3 } else { 1e-10 };
+                assert_eq!(objective.dim(), 2);
+                assert_abs_diff_eq!(
+                    objective.value(&x),
+                    crate::test_math_funcs::rosenbrock(&x, a, b),
+                    epsilon = 1e-12
+                );
+
+                let mut grad = [0.; 2];
+                objective.gradient(&x, &mut grad);
+                let mut hess = [0.; 4];
+                objective.hessian(&x, &mut hess);
+                for i in 0..2 {
+                    assert_abs_diff_eq!(grad[i], expected_grad[i], epsilon = tol);
+                    for j in 0..2 {
+                        assert_abs_diff_eq!(hess[i * 2 + j], expected_hess[i][j], epsilon = tol);
+                    }
+                }
+            }
+        }
+
+        assert_eq!(
+            analytic.evaluations(),
+            Evaluations {
+                value: 3,
+                gradient: 3,
+                hessian: 3
+            }
+        );
+        assert_eq!(graph.evaluations().gradient, 3);
+        assert_eq!(numeric.evaluations().gradient, 3);
+        assert!(numeric.evaluations().value > 3 + 3 * 4);
+    }
+
+    #[test]
+    fn test_default_derivatives() {
+        struct Quadratic;
+        impl Objective for Quadratic {
+            fn dim(&self) -> usize {
+                3
+            }
+
+            fn value(&self, x: &[f64]) -> f64 {
+                x[0] * x[0] + 2. * x[1] * x[1] + 3. * x[0] * x[2]
+            }
+        }
+
+        let x = [1., -2., 0.5];
+        let mut grad = [0.; 3];
+        Quadratic.gradient(&x, &mut grad);
+        let mut hess = [0.; 9];
+        Quadratic.hessian(&x, &mut hess);
+
+        let expected_grad = [2. * x[0] + 3. * x[2], 4. * x[1], 3. * x[0]];
+        let expected_hess = [2., 0., 3., 0., 4., 0., 3., 0., 0.];
+        for i in 0..3 {
+            assert_abs_diff_eq!(grad[i], expected_grad[i], epsilon = 1e-8);
+        }
+        for i in 0..9 {
+            assert_abs_diff_eq!(hess[i], expected_hess[i], epsilon = 1e-4);
+        }
+        assert_eq!(Quadratic.evaluations(), Evaluations::default());
+    }
+
+    #[test]
+    fn test_graph_objective_unused_variable() {
+        let objective = GraphObjective::new(3, |x| x[0].sin() * &x[2]);
+        let x = [0.4, 7., -1.5];
+        let mut grad = [1.; 3];
+        let value = objective.value_and_gradient(&x, &mut grad);
+        let mut hess = [1.; 9];
+        objective.hessian(&x, &mut hess);
+
+        assert_abs_diff_eq!(value, 0.4_f64.sin() * -1.5);
+        assert_eq!(grad, [0.4_f64.cos() * -1.5, 0., 0.4_f64.sin()]);
+        assert_eq!(
+            hess,
+            [
+                -(0.4_f64.sin()) * -1.5,
+                0.,
+                0.4_f64.cos(),
+                0.,
+                0.,
+                0.,
+                0.4_f64.cos(),
+                0.,
+                0.
+            ]
+        );
+    }
+
+    #[test]
+    fn test_graph_objective_constants() {
+        // a constant recorded on the tape of the variables is not one of them
+        let objective = GraphObjective::new(2, |x| {
+            let c = x[0].tape().var(3.);
+            &c * &x[0] * &x[1] + 2. * &x[1]
+        });
+        let x = [0.5, -1.];
+        let mut grad = [0.; 2];
+        let value = objective.value_and_gradient(&x, &mut grad);
+        let mut hess = [0.; 4];
+        objective.hessian(&x, &mut hess);
+
+        assert_abs_diff_eq!(value, -1.5 - 2.);
+        assert_eq!(grad, [-3., 3.5]);
+        assert_eq!(hess, [0., 3., 3., 0.]);
+    }
+}
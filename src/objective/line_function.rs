@@ -0,0 +1,210 @@
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+
+use super::{Objective, UnivariateFn};
+
+struct Sample {
+    value: f64,
+    grad: Option<Vec<f64>>,
+}
+
+/// An objective restricted to the line `x + alpha d`, as a function of `alpha`.
+///
+/// Evaluations are cached by `alpha`, so searches revisiting a step or asking for
+/// the derivative where the value is known do not call the objective again.
+pub struct LineFunction<'a> {
+    objective: &'a dyn Objective,
+    x: Vec<f64>,
+    d: Vec<f64>,
+    samples: RefCell<HashMap<u64, Sample>>,
+    evaluations: Cell<usize>,
+}
+
+impl<'a> LineFunction<'a> {
+    pub fn new(objective: &'a dyn Objective, x: &[f64], d: &[f64]) -> Self {
+        assert!(x.len() == objective.dim() && d.len() == x.len());
+        Self {
+            objective,
+            x: x.to_vec(),
+            d: d.to_vec(),
+            samples: RefCell::new(HashMap::new()),
+            evaluations: Cell::new(0),
+        }
+    }
+
+    /// Seeds the cache with the value and gradient already known at `x`.
+    pub fn with_start(self, fx: f64, grad: &[f64]) -> Self {
+        assert!(grad.len() == self.x.len());
+        self.samples.borrow_mut().insert(
+            0f64.to_bits(),
+            Sample {
+                value: fx,
+                grad: Some(grad.to_vec()),
+            },
+        );
+        self
+    }
+
+    pub fn objective(&self) -> &'a dyn Objective {
+        self.objective
+    }
+
+    pub fn origin(&self) -> &[f64] {
+        &self.x
+    }
+
+    pub fn direction(&self) -> &[f64] {
+        &self.d
+    }
+
+    /// `x + alpha d`
+    pub fn point(&self, alpha: f64) -> Vec<f64> {
+        self.x
+            .iter()
+            .zip(&self.d)
+            .map(|(x, d)| x + alpha * d)
+            .collect()
+    }
+
+    pub fn value(&self, alpha: f64) -> f64 {
+        if let Some(sample) = self.samples.borrow().get(&alpha.to_bits()) {
+            return sample.value;
+        }
+        self.evaluations.set(self.evaluations.get() + 1);
+        let value = self.objective.value(&self.point(alpha));
+        self.samples
+            .borrow_mut()
+            .insert(alpha.to_bits(), Sample { value, grad: None });
+        value
+    }
+
+    pub fn value_and_gradient(&self, alpha: f64) -> (f64, Vec<f64>) {
+        let known = match self.samples.borrow().get(&alpha.to_bits()) {
+            Some(Sample {
+                value,
+                grad: Some(grad),
+            }) => return (*value, grad.clone()),
+            sample => sample.map(|s| s.value),
+        };
+
+        self.evaluations.set(self.evaluations.get() + 1);
+        let point = self.point(alpha);
+        let mut grad = vec![0.; point.len()];
+        let value = match known {
+            Some(value) => {
+                self.objective.gradient(&point, &mut grad);
+                value
+            }
+            None => self.objective.value_and_gradient(&point, &mut grad),
+        };
+        self.samples.borrow_mut().insert(
+            alpha.to_bits(),
+            Sample {
+                value,
+                grad: Some(grad.clone()),
+            },
+        );
+        (value, grad)
+    }
+
+    pub fn gradient(&self, alpha: f64) -> Vec<f64> {
+        self.value_and_gradient(alpha).1
+    }
+
+    /// `grad f(x + alpha d) . d`
+    pub fn derivative(&self, alpha: f64) -> f64 {
+        self.slope(&self.gradient(alpha))
+    }
+
+    /// Value and `derivative` from a single evaluation.
+    pub fn value_and_derivative(&self, alpha: f64) -> (f64, f64) {
+        let (value, grad) = self.value_and_gradient(alpha);
+        (value, self.slope(&grad))
+    }
+
+    fn slope(&self, grad: &[f64]) -> f64 {
+        grad.iter().zip(&self.d).map(|(g, d)| g * d).sum()
+    }
+
+    /// Calls to the objective so far, a value and gradient computed together count once.
+    pub fn evaluations(&self) -> usize {
+        self.evaluations.get()
+    }
+}
+
+impl UnivariateFn for LineFunction<'_> {
+    fn value(&self, alpha: f64) -> f64 {
+        LineFunction::value(self, alpha)
+    }
+
+    fn derivative(&self, alpha: f64) -> f64 {
+        LineFunction::derivative(self, alpha)
+    }
+
+    fn value_and_derivative(&self, alpha: f64) -> (f64, f64) {
+        LineFunction::value_and_derivative(self, alpha)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bracketing::{derivative_bisection, LineMinimizer};
+    use crate::test_math_funcs::rosenbrock_objective;
+
+    #[test]
+    fn caches_and_exposes_points() {
+        let objective = rosenbrock_objective(1., 100.);
+        let x = [-1.4, 0.9];
+        let mut g = [0.; 2];
+        objective.gradient(&x, &mut g);
+        let d = [-g[0], -g[1]];
+        let line = LineFunction::new(&objective, &x, &d);
+
+        assert_eq!(line.value(0.), objective.value(&x));
+        assert_eq!(line.derivative(0.), -(g[0] * g[0] + g[1] * g[1]));
+        assert_eq!(line.value(0.), objective.value(&x));
+        assert_eq!(line.evaluations(), 2);
+        assert_eq!(line.point(0.5), vec![x[0] + 0.5 * d[0], x[1] + 0.5 * d[1]]);
+
+        let (counted, before) = (objective.evaluations(), line.evaluations());
+        let res = LineMinimizer::new()
+            .with_step(1e-6)
+            .minimize(&line, 0.)
+            .unwrap();
+        assert_eq!(
+            objective.evaluations().value - counted.value,
+            line.evaluations() - before
+        );
+        assert_eq!(line.value(res.x), res.fx);
+        assert_eq!(objective.value(&line.point(res.x)), res.fx);
+
+        let stationary =
+            derivative_bisection(&|alpha| line.derivative(alpha), res.bracket, 1e-12, 100).unwrap();
+        assert!((stationary.x - res.x).abs() < 1e-5);
+    }
+
+    #[test]
+    fn seeded_start() {
+        let objective = rosenbrock_objective(1., 100.);
+        let line = LineFunction::new(&objective, &[0., 0.], &[1., 0.]).with_start(1., &[-2., 0.]);
+        assert_eq!(line.derivative(0.), -2.);
+        assert_eq!(line.evaluations(), 0);
+        assert_eq!(UnivariateFn::derivative(&line, 0.5), line.derivative(0.5));
+        assert_eq!(line.evaluations(), 1);
+    }
+
+    #[test]
+    fn value_and_derivative_in_one_evaluation() {
+        let objective = rosenbrock_objective(1., 100.);
+        let line = LineFunction::new(&objective, &[-1.4, 0.9], &[1., 0.5]);
+        let (value, derivative) = UnivariateFn::value_and_derivative(&line, 0.25);
+        assert_eq!(line.evaluations(), 1);
+        assert_eq!(objective.evaluations().gradient, 1);
+        assert_eq!(
+            (line.value(0.25), line.derivative(0.25)),
+            (value, derivative)
+        );
+        assert_eq!(line.evaluations(), 1);
+    }
+}
@@ -0,0 +1,370 @@
+mod adaptive;
+mod conjugate_gradient;
+mod gradient_descent;
+mod linalg;
+mod momentum;
+mod newton;
+mod quasi_newton;
+
+pub use adaptive::{
+    AdaMax, Adadelta, Adagrad, Adam, Adaptive, Hypergradient, RmsProp, Sgd, StepOptimizer,
+};
+pub use conjugate_gradient::{Beta, ConjugateGradient, Restart};
+pub use gradient_descent::{GradientDescent, StepSize};
+pub use momentum::{Momentum, NesterovMomentum};
+pub use newton::{Modification, Newton};
+pub use quasi_newton::{Bfgs, CurvatureGuard, Dfp, Lbfgs, Sr1};
+
+use std::fmt::Display;
+
+use crate::bracketing::{BracketError, LineMinimizer};
+use crate::line_search::{Backtracking, HagerZhang, LineSearch, LineSearchError, StrongWolfe};
+use crate::objective::{LineFunction, Objective};
+
+/// Stopping criteria, checked after every iteration in this order, and how much of the
+/// run to record.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Convergence {
+    /// Stop once `|grad f(x)|` is at most this.
+    pub gradient_tol: f64,
+    /// Stop once a step `|x_k+1 - x_k|` is at most this.
+    pub step_tol: f64,
+    /// Stop once an iteration changes `f` by at most this.
+    pub value_tol: f64,
+    pub max_iter: usize,
+    /// Record every iterate in `trajectory` and `values`. Without it only the current
+    /// point is kept, so memory stays `O(n)` on large problems.
+    pub keep_trajectory: bool,
+}
+
+impl Default for Convergence {
+    fn default() -> Self {
+        Self {
+            gradient_tol: 1e-6,
+            step_tol: 1e-12,
+            value_tol: 0.,
+            max_iter: 1000,
+            keep_trajectory: true,
+        }
+    }
+}
+
+/// Criterion an optimizer stopped on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Termination {
+    Gradient,
+    Step,
+    Value,
+    MaxIterations,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct OptimizeResult {
+    pub x: Vec<f64>,
+    pub fx: f64,
+    pub grad: Vec<f64>,
+    pub iterations: usize,
+    pub termination: Termination,
+    /// Every iterate from the starting point on, `x` is the last one. Only `x` unless
+    /// `Convergence::keep_trajectory` is set.
+    pub trajectory: Vec<Vec<f64>>,
+    /// `f` at each point of the trajectory.
+    pub values: Vec<f64>,
+}
+
+impl OptimizeResult {
+    /// Stopped on a tolerance rather than the iteration limit.
+    pub fn converged(&self) -> bool {
+        self.termination != Termination::MaxIterations
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum OptimizeError {
+    /// The objective or its gradient is not finite at an iterate.
+    NonFinite {
+        iteration: usize,
+        x: Vec<f64>,
+    },
+    /// The line search of an iteration failed.
+    LineSearch {
+        iteration: usize,
+        error: LineSearchError,
+    },
+    /// The exact line minimization of an iteration failed.
+    Bracket {
+        iteration: usize,
+        error: BracketError,
+    },
+    InvalidParameter {
+        name: &'static str,
+        value: f64,
+    },
+    /// The starting point does not match the dimension of the objective.
+    Dimension {
+        expected: usize,
+        found: usize,
+    },
+}
+
+impl Display for OptimizeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NonFinite { iteration, x } => {
+                write!(
+                    f,
+                    "non-finite objective at iteration {iteration}, x = {x:?}"
+                )
+            }
+            Self::LineSearch { iteration, error } => {
+                write!(f, "line search failed at iteration {iteration}: {error}")
+            }
+            Self::Bracket { iteration, error } => {
+                write!(
+                    f,
+                    "line minimization failed at iteration {iteration}: {error}"
+                )
+            }
+            Self::InvalidParameter { name, value } => write!(f, "invalid {name} = {value}"),
+            Self::Dimension { expected, found } => {
+                write!(
+                    f,
+                    "starting point of dimension {found}, expected {expected}"
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for OptimizeError {}
+
+/// Step length selection of optimizers that search along a direction.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LineStep {
+    /// Minimizes `f` along the direction.
+    Exact(LineMinimizer),
+    Backtracking(Backtracking),
+    StrongWolfe(StrongWolfe),
+    HagerZhang(HagerZhang),
+}
+
+impl From<LineMinimizer> for LineStep {
+    fn from(minimizer: LineMinimizer) -> Self {
+        Self::Exact(minimizer)
+    }
+}
+
+impl From<Backtracking> for LineStep {
+    fn from(search: Backtracking) -> Self {
+        Self::Backtracking(search)
+    }
+}
+
+impl From<StrongWolfe> for LineStep {
+    fn from(search: StrongWolfe) -> Self {
+        Self::StrongWolfe(search)
+    }
+}
+
+impl From<HagerZhang> for LineStep {
+    fn from(search: HagerZhang) -> Self {
+        Self::HagerZhang(search)
+    }
+}
+
+/// Minimization of an `Objective` from a starting point.
+pub trait Optimizer {
+    fn minimize(
+        &self,
+        objective: &dyn Objective,
+        x0: &[f64],
+    ) -> Result<OptimizeResult, OptimizeError>;
+}
+
+fn dot(a: &[f64], b: &[f64]) -> f64 {
+    a.iter().zip(b).map(|(a, b)| a * b).sum()
+}
+
+fn norm(a: &[f64]) -> f64 {
+    dot(a, a).sqrt()
+}
+
+/// Minimum of `line`, over `alpha >= 0` unless `minimizer` has bounds.
+fn exact_step(
+    line: &LineFunction,
+    minimizer: &LineMinimizer,
+    iteration: usize,
+) -> Result<f64, OptimizeError> {
+    let minimizer = match minimizer.bounds() {
+        Some(_) => *minimizer,
+        None => minimizer.with_bounds(0., f64::MAX),
+    };
+    minimizer
+        .minimize(line, 0.)
+        .map(|res| res.x)
+        .map_err(|error| OptimizeError::Bracket { iteration, error })
+}
+
+/// Iterates of a run with the current gradient, checked against `Convergence`.
+struct Run {
+    convergence: Convergence,
+    /// Points recorded so far, the starting point included.
+    points: usize,
+    /// All iterates, or the last two without `keep_trajectory`.
+    trajectory: Vec<Vec<f64>>,
+    values: Vec<f64>,
+    grad: Vec<f64>,
+}
+
+impl Run {
+    /// Evaluates the objective at `x0`.
+    fn start(
+        objective: &dyn Objective,
+        x0: &[f64],
+        convergence: Convergence,
+    ) -> Result<Self, OptimizeError> {
+        if x0.len() != objective.dim() {
+            return Err(OptimizeError::Dimension {
+                expected: objective.dim(),
+                found: x0.len(),
+            });
+        }
+        let mut grad = vec![0.; x0.len()];
+        let fx = objective.value_and_gradient(x0, &mut grad);
+        let mut run = Self {
+            convergence,
+            points: 0,
+            trajectory: vec![],
+            values: vec![],
+            grad: vec![],
+        };
+        run.push(x0.to_vec(), fx, grad)?;
+        Ok(run)
+    }
+
+    fn x(&self) -> &[f64] {
+        self.trajectory.last().unwrap()
+    }
+
+    fn fx(&self) -> f64 {
+        *self.values.last().unwrap()
+    }
+
+    fn grad(&self) -> &[f64] {
+        &self.grad
+    }
+
+    /// Iterations made so far.
+    fn iteration(&self) -> usize {
+        self.points - 1
+    }
+
+    fn push(&mut self, x: Vec<f64>, fx: f64, grad: Vec<f64>) -> Result<(), OptimizeError> {
+        if !fx.is_finite() || grad.iter().any(|g| !g.is_finite()) {
+            return Err(OptimizeError::NonFinite {
+                iteration: self.points,
+                x,
+            });
+        }
+        if !self.convergence.keep_trajectory && self.trajectory.len() == 2 {
+            self.trajectory.remove(0);
+            self.values.remove(0);
+        }
+        self.trajectory.push(x);
+        self.values.push(fx);
+        self.grad = grad;
+        self.points += 1;
+        Ok(())
+    }
+
+    /// Evaluates the objective at `x` and records it as the next iterate.
+    fn push_point(&mut self, objective: &dyn Objective, x: Vec<f64>) -> Result<(), OptimizeError> {
+        let mut grad = vec![0.; x.len()];
+        let fx = objective.value_and_gradient(&x, &mut grad);
+        self.push(x, fx, grad)
+    }
+
+    /// Moves to the minimum along `d`, over `alpha >= 0` unless `minimizer` has bounds.
+    fn line_minimize(
+        &mut self,
+        objective: &dyn Objective,
+        minimizer: &LineMinimizer,
+        d: &[f64],
+    ) -> Result<(), OptimizeError> {
+        let line = LineFunction::new(objective, self.x(), d).with_start(self.fx(), self.grad());
+        let alpha = exact_step(&line, minimizer, self.iteration() + 1)?;
+        let (fx, grad) = line.value_and_gradient(alpha);
+        self.push(line.point(alpha), fx, grad)
+    }
+
+    /// Moves to the step `search` accepts along `d`.
+    fn line_search(
+        &mut self,
+        objective: &dyn Objective,
+        search: &dyn LineSearch,
+        d: &[f64],
+    ) -> Result<(), OptimizeError> {
+        let res = search
+            .search_from(objective, self.x(), self.fx(), self.grad(), d)
+            .map_err(|error| OptimizeError::LineSearch {
+                iteration: self.iteration() + 1,
+                error,
+            })?;
+        self.push(res.x, res.fx, res.grad)
+    }
+
+    /// Moves along `d` with the step `line_step` selects.
+    fn line_step(
+        &mut self,
+        objective: &dyn Objective,
+        line_step: &LineStep,
+        d: &[f64],
+    ) -> Result<(), OptimizeError> {
+        match line_step {
+            LineStep::Exact(minimizer) => self.line_minimize(objective, minimizer, d),
+            LineStep::Backtracking(search) => self.line_search(objective, search, d),
+            LineStep::StrongWolfe(search) => self.line_search(objective, search, d),
+            LineStep::HagerZhang(search) => self.line_search(objective, search, d),
+        }
+    }
+
+    fn termination(&self) -> Option<Termination> {
+        let c = &self.convergence;
+        if norm(&self.grad) <= c.gradient_tol {
+            return Some(Termination::Gradient);
+        }
+        if let [.., prev, x] = &self.trajectory[..] {
+            let step = prev
+                .iter()
+                .zip(x)
+                .map(|(a, b)| (b - a).powi(2))
+                .sum::<f64>();
+            if step.sqrt() <= c.step_tol {
+                return Some(Termination::Step);
+            }
+            let [.., f_prev, fx] = self.values[..] else {
+                unreachable!()
+            };
+            if (fx - f_prev).abs() <= c.value_tol {
+                return Some(Termination::Value);
+            }
+        }
+        (self.iteration() >= c.max_iter).then_some(Termination::MaxIterations)
+    }
+
+    fn finish(mut self, termination: Termination) -> OptimizeResult {
+        if !self.convergence.keep_trajectory {
+            self.trajectory.drain(..self.trajectory.len() - 1);
+            self.values.drain(..self.values.len() - 1);
+        }
+        OptimizeResult {
+            x: self.trajectory.last().unwrap().clone(),
+            fx: self.fx(),
+            grad: std::mem::take(&mut self.grad),
+            iterations: self.iteration(),
+            termination,
+            trajectory: self.trajectory,
+            values: self.values,
+        }
+    }
+}
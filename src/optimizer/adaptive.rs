@@ -0,0 +1,660 @@
+use super::{dot, Convergence, OptimizeError, OptimizeResult, Optimizer, Run};
+use crate::autograd::compute_graph::node::{Node, NodeGradPair};
+use crate::objective::Objective;
+
+/// Optimizer that updates the parameters in place from one gradient at a time, keeping
+/// whatever state it accumulates between steps.
+///
+/// ```
+/// use std::rc::Rc;
+///
+/// use optimize_examples::autograd::compute_graph::{node::Node, tape::Tape};
+/// use optimize_examples::optimizer::{Adam, StepOptimizer};
+///
+/// let mut adam = Adam::new(0.1);
+/// let mut x = vec![1., -1.];
+/// for _ in 0..500 {
+///     let tape = Tape::new();
+///     let vars = x.iter().map(|&x| tape.var(x)).collect::<Vec<_>>();
+///     let loss = (&vars[0] - 0.5).powi(2) + (&vars[1] * &vars[1]).exp();
+///     adam.step_pairs(&mut x, &vars, &Node::auto_grad(Rc::new(loss)));
+/// }
+/// assert!((x[0] - 0.5).abs() < 1e-3 && x[1].abs() < 1e-3);
+/// ```
+pub trait StepOptimizer {
+    /// Moves `x` given the gradient at `x`.
+    fn step(&mut self, x: &mut [f64], grad: &[f64]);
+
+    /// Clears the state accumulated by `step`.
+    fn reset(&mut self);
+
+    fn learning_rate(&self) -> f64;
+    fn set_learning_rate(&mut self, learning_rate: f64);
+
+    /// `step` with the `Node::auto_grad` results of a graph recorded from `vars` at `x`.
+    fn step_pairs(&mut self, x: &mut [f64], vars: &[Node], pairs: &[NodeGradPair]) {
+        self.step(x, &NodeGradPair::gradient(vars, pairs));
+    }
+}
+
+/// Zeroed state of the length of `x`, keeping it while the length matches.
+fn state(v: &mut Vec<f64>, x: &[f64]) {
+    if v.len() != x.len() {
+        *v = vec![0.; x.len()];
+    }
+}
+
+/// Stochastic gradient descent, `x -= learning_rate grad`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sgd {
+    learning_rate: f64,
+}
+
+impl Sgd {
+    pub fn new(learning_rate: f64) -> Self {
+        Self { learning_rate }
+    }
+}
+
+impl StepOptimizer for Sgd {
+    fn step(&mut self, x: &mut [f64], grad: &[f64]) {
+        x.iter_mut()
+            .zip(grad)
+            .for_each(|(x, g)| *x -= self.learning_rate * g);
+    }
+
+    fn reset(&mut self) {}
+
+    fn learning_rate(&self) -> f64 {
+        self.learning_rate
+    }
+
+    fn set_learning_rate(&mut self, learning_rate: f64) {
+        self.learning_rate = learning_rate;
+    }
+}
+
+/// Scales each coordinate by the root of its accumulated squared gradients.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Adagrad {
+    learning_rate: f64,
+    epsilon: f64,
+    sum_sq: Vec<f64>,
+}
+
+impl Default for Adagrad {
+    fn default() -> Self {
+        Self::new(0.01)
+    }
+}
+
+impl Adagrad {
+    pub fn new(learning_rate: f64) -> Self {
+        Self {
+            learning_rate,
+            epsilon: 1e-8,
+            sum_sq: vec![],
+        }
+    }
+
+    pub fn with_epsilon(mut self, epsilon: f64) -> Self {
+        self.epsilon = epsilon;
+        self
+    }
+}
+
+impl StepOptimizer for Adagrad {
+    fn step(&mut self, x: &mut [f64], grad: &[f64]) {
+        state(&mut self.sum_sq, x);
+        for ((x, g), s) in x.iter_mut().zip(grad).zip(&mut self.sum_sq) {
+            *s += g * g;
+            *x -= self.learning_rate * g / (s.sqrt() + self.epsilon);
+        }
+    }
+
+    fn reset(&mut self) {
+        self.sum_sq.clear();
+    }
+
+    fn learning_rate(&self) -> f64 {
+        self.learning_rate
+    }
+
+    fn set_learning_rate(&mut self, learning_rate: f64) {
+        self.learning_rate = learning_rate;
+    }
+}
+
+/// Scales each coordinate by the root of a moving average of its squared gradients.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RmsProp {
+    learning_rate: f64,
+    /// Decay of the moving average.
+    rho: f64,
+    epsilon: f64,
+    mean_sq: Vec<f64>,
+}
+
+impl Default for RmsProp {
+    fn default() -> Self {
+        Self::new(0.001)
+    }
+}
+
+impl RmsProp {
+    pub fn new(learning_rate: f64) -> Self {
+        Self {
+            learning_rate,
+            rho: 0.9,
+            epsilon: 1e-8,
+            mean_sq: vec![],
+        }
+    }
+
+    pub fn with_rho(mut self, rho: f64) -> Self {
+        self.rho = rho;
+        self
+    }
+
+    pub fn with_epsilon(mut self, epsilon: f64) -> Self {
+        self.epsilon = epsilon;
+        self
+    }
+}
+
+impl StepOptimizer for RmsProp {
+    fn step(&mut self, x: &mut [f64], grad: &[f64]) {
+        state(&mut self.mean_sq, x);
+        for ((x, g), s) in x.iter_mut().zip(grad).zip(&mut self.mean_sq) {
+            *s = self.rho * *s + (1. - self.rho) * g * g;
+            *x -= self.learning_rate * g / (s.sqrt() + self.epsilon);
+        }
+    }
+
+    fn reset(&mut self) {
+        self.mean_sq.clear();
+    }
+
+    fn learning_rate(&self) -> f64 {
+        self.learning_rate
+    }
+
+    fn set_learning_rate(&mut self, learning_rate: f64) {
+        self.learning_rate = learning_rate;
+    }
+}
+
+/// RMSProp with the learning rate replaced by the root of a moving average of the
+/// squared steps, the learning rate only scales the result.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Adadelta {
+    learning_rate: f64,
+    rho: f64,
+    epsilon: f64,
+    mean_sq: Vec<f64>,
+    mean_sq_step: Vec<f64>,
+}
+
+impl Default for Adadelta {
+    fn default() -> Self {
+        Self::new(1.)
+    }
+}
+
+impl Adadelta {
+    pub fn new(learning_rate: f64) -> Self {
+        Self {
+            learning_rate,
+            rho: 0.95,
+            epsilon: 1e-6,
+            mean_sq: vec![],
+            mean_sq_step: vec![],
+        }
+    }
+
+    pub fn with_rho(mut self, rho: f64) -> Self {
+        self.rho = rho;
+        self
+    }
+
+    pub fn with_epsilon(mut self, epsilon: f64) -> Self {
+        self.epsilon = epsilon;
+        self
+    }
+}
+
+impl StepOptimizer for Adadelta {
+    fn step(&mut self, x: &mut [f64], grad: &[f64]) {
+        state(&mut self.mean_sq, x);
+        state(&mut self.mean_sq_step, x);
+        let rho = self.rho;
+        for (((x, g), s), u) in x
+            .iter_mut()
+            .zip(grad)
+            .zip(&mut self.mean_sq)
+            .zip(&mut self.mean_sq_step)
+        {
+            *s = rho * *s + (1. - rho) * g * g;
+            let dx = -((*u + self.epsilon) / (*s + self.epsilon)).sqrt() * g;
+            *u = rho * *u + (1. - rho) * dx * dx;
+            *x += self.learning_rate * dx;
+        }
+    }
+
+    fn reset(&mut self) {
+        self.mean_sq.clear();
+        self.mean_sq_step.clear();
+    }
+
+    fn learning_rate(&self) -> f64 {
+        self.learning_rate
+    }
+
+    fn set_learning_rate(&mut self, learning_rate: f64) {
+        self.learning_rate = learning_rate;
+    }
+}
+
+/// Bias corrected moving averages of the gradient and its square.
+///
+/// With AMSGrad the running maximum of the squared gradient average is used instead.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Adam {
+    learning_rate: f64,
+    beta1: f64,
+    beta2: f64,
+    epsilon: f64,
+    amsgrad: bool,
+    m: Vec<f64>,
+    v: Vec<f64>,
+    v_max: Vec<f64>,
+    t: i32,
+}
+
+impl Default for Adam {
+    fn default() -> Self {
+        Self::new(0.001)
+    }
+}
+
+impl Adam {
+    pub fn new(learning_rate: f64) -> Self {
+        Self {
+            learning_rate,
+            beta1: 0.9,
+            beta2: 0.999,
+            epsilon: 1e-8,
+            amsgrad: false,
+            m: vec![],
+            v: vec![],
+            v_max: vec![],
+            t: 0,
+        }
+    }
+
+    pub fn with_betas(mut self, beta1: f64, beta2: f64) -> Self {
+        self.beta1 = beta1;
+        self.beta2 = beta2;
+        self
+    }
+
+    pub fn with_epsilon(mut self, epsilon: f64) -> Self {
+        self.epsilon = epsilon;
+        self
+    }
+
+    pub fn with_amsgrad(mut self, amsgrad: bool) -> Self {
+        self.amsgrad = amsgrad;
+        self
+    }
+}
+
+impl StepOptimizer for Adam {
+    fn step(&mut self, x: &mut [f64], grad: &[f64]) {
+        state(&mut self.m, x);
+        state(&mut self.v, x);
+        state(&mut self.v_max, x);
+        self.t += 1;
+        let (beta1, beta2) = (self.beta1, self.beta2);
+        let correction1 = 1. - beta1.powi(self.t);
+        let correction2 = 1. - beta2.powi(self.t);
+
+        for (i, (x, g)) in x.iter_mut().zip(grad).enumerate() {
+            self.m[i] = beta1 * self.m[i] + (1. - beta1) * g;
+            self.v[i] = beta2 * self.v[i] + (1. - beta2) * g * g;
+            let v = if self.amsgrad {
+                self.v_max[i] = self.v_max[i].max(self.v[i]);
+                self.v_max[i]
+            } else {
+                self.v[i]
+            };
+            let m_hat = self.m[i] / correction1;
+            *x -= self.learning_rate * m_hat / ((v / correction2).sqrt() + self.epsilon);
+        }
+    }
+
+    fn reset(&mut self) {
+        self.m.clear();
+        self.v.clear();
+        self.v_max.clear();
+        self.t = 0;
+    }
+
+    fn learning_rate(&self) -> f64 {
+        self.learning_rate
+    }
+
+    fn set_learning_rate(&mut self, learning_rate: f64) {
+        self.learning_rate = learning_rate;
+    }
+}
+
+/// Adam with the squared gradient average replaced by an exponentially weighted
+/// infinity norm.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AdaMax {
+    learning_rate: f64,
+    beta1: f64,
+    beta2: f64,
+    epsilon: f64,
+    m: Vec<f64>,
+    u: Vec<f64>,
+    t: i32,
+}
+
+impl Default for AdaMax {
+    fn default() -> Self {
+        Self::new(0.002)
+    }
+}
+
+impl AdaMax {
+    pub fn new(learning_rate: f64) -> Self {
+        Self {
+            learning_rate,
+            beta1: 0.9,
+            beta2: 0.999,
+            epsilon: 1e-8,
+            m: vec![],
+            u: vec![],
+            t: 0,
+        }
+    }
+
+    pub fn with_betas(mut self, beta1: f64, beta2: f64) -> Self {
+        self.beta1 = beta1;
+        self.beta2 = beta2;
+        self
+    }
+
+    pub fn with_epsilon(mut self, epsilon: f64) -> Self {
+        self.epsilon = epsilon;
+        self
+    }
+}
+
+impl StepOptimizer for AdaMax {
+    fn step(&mut self, x: &mut [f64], grad: &[f64]) {
+        state(&mut self.m, x);
+        state(&mut self.u, x);
+        self.t += 1;
+        let alpha = self.learning_rate / (1. - self.beta1.powi(self.t));
+
+        for (((x, g), m), u) in x.iter_mut().zip(grad).zip(&mut self.m).zip(&mut self.u) {
+            *m = self.beta1 * *m + (1. - self.beta1) * g;
+            *u = (self.beta2 * *u).max(g.abs());
+            *x -= alpha * *m / (*u + self.epsilon);
+        }
+    }
+
+    fn reset(&mut self) {
+        self.m.clear();
+        self.u.clear();
+        self.t = 0;
+    }
+
+    fn learning_rate(&self) -> f64 {
+        self.learning_rate
+    }
+
+    fn set_learning_rate(&mut self, learning_rate: f64) {
+        self.learning_rate = learning_rate;
+    }
+}
+
+/// Hypergradient descent on the learning rate of `S`, as in Baydin et al. (2018).
+///
+/// Before each step the learning rate moves by `-beta` times its derivative
+/// `grad f(x_k) . u_k-1`, where `u_k-1` is the previous step divided by its learning rate.
+/// `Hypergradient<Sgd>` is SGD-HD and `Hypergradient<Adam>` is Adam-HD.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Hypergradient<S> {
+    inner: S,
+    beta: f64,
+    initial_rate: f64,
+    update: Vec<f64>,
+}
+
+impl<S: StepOptimizer> Hypergradient<S> {
+    pub fn new(inner: S, beta: f64) -> Self {
+        Self {
+            initial_rate: inner.learning_rate(),
+            inner,
+            beta,
+            update: vec![],
+        }
+    }
+
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+}
+
+impl<S: StepOptimizer> StepOptimizer for Hypergradient<S> {
+    fn step(&mut self, x: &mut [f64], grad: &[f64]) {
+        let mut alpha = self.inner.learning_rate();
+        if self.update.len() == x.len() {
+            alpha -= self.beta * dot(grad, &self.update);
+            self.inner.set_learning_rate(alpha);
+        }
+
+        let x0 = x.to_vec();
+        self.inner.step(x, grad);
+        if alpha != 0. {
+            self.update = x.iter().zip(&x0).map(|(x, x0)| (x - x0) / alpha).collect();
+        }
+    }
+
+    /// Also restores the learning rate `S` started with.
+    fn reset(&mut self) {
+        self.inner.reset();
+        self.inner.set_learning_rate(self.initial_rate);
+        self.update.clear();
+    }
+
+    fn learning_rate(&self) -> f64 {
+        self.inner.learning_rate()
+    }
+
+    fn set_learning_rate(&mut self, learning_rate: f64) {
+        self.inner.set_learning_rate(learning_rate);
+    }
+}
+
+/// Runs a `StepOptimizer` on an objective with the stopping criteria and trajectory of
+/// the other optimizers, every run starts from a reset copy.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Adaptive<S> {
+    optimizer: S,
+    convergence: Convergence,
+}
+
+impl<S: StepOptimizer + Clone> Adaptive<S> {
+    pub fn new(optimizer: S) -> Self {
+        Self {
+            optimizer,
+            convergence: Convergence::default(),
+        }
+    }
+
+    pub fn with_convergence(mut self, convergence: Convergence) -> Self {
+        self.convergence = convergence;
+        self
+    }
+}
+
+impl<S: StepOptimizer + Clone> Optimizer for Adaptive<S> {
+    fn minimize(
+        &self,
+        objective: &dyn Objective,
+        x0: &[f64],
+    ) -> Result<OptimizeResult, OptimizeError> {
+        let mut optimizer = self.optimizer.clone();
+        optimizer.reset();
+        let mut run = Run::start(objective, x0, self.convergence)?;
+
+        loop {
+            if let Some(termination) = run.termination() {
+                return Ok(run.finish(termination));
+            }
+            let mut x = run.x().to_vec();
+            optimizer.step(&mut x, run.grad());
+            run.push_point(objective, x)?;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::autograd::compute_graph::tape::Tape;
+    use crate::objective::FnObjective;
+    use crate::optimizer::Termination;
+
+    fn quadratic() -> FnObjective {
+        FnObjective::new(2, |x| x[0] * x[0] + 10. * x[1] * x[1])
+            .with_gradient(|x, g| g.copy_from_slice(&[2. * x[0], 20. * x[1]]))
+    }
+
+    fn minimize(optimizer: impl StepOptimizer + Clone) -> Result<OptimizeResult, OptimizeError> {
+        Adaptive::new(optimizer)
+            .with_convergence(Convergence {
+                gradient_tol: 1e-4,
+                max_iter: 20000,
+                ..Default::default()
+            })
+            .minimize(&quadratic(), &[1., 1.])
+    }
+
+    #[test]
+    fn test_optimizers_on_quadratic() {
+        let results = [
+            ("sgd", minimize(Sgd::new(0.05))),
+            ("adagrad", minimize(Adagrad::new(0.5))),
+            ("rmsprop", minimize(RmsProp::new(0.01))),
+            ("adadelta", minimize(Adadelta::default().with_epsilon(1e-4))),
+            ("adam", minimize(Adam::new(0.05))),
+            ("amsgrad", minimize(Adam::new(0.05).with_amsgrad(true))),
+            ("adamax", minimize(AdaMax::new(0.05))),
+            ("sgd-hd", minimize(Hypergradient::new(Sgd::new(0.01), 1e-5))),
+            (
+                "adam-hd",
+                minimize(Hypergradient::new(Adam::new(0.05), 1e-4)),
+            ),
+        ];
+        for (name, res) in results {
+            let res = res.unwrap_or_else(|err| panic!("{name}: {err}"));
+            assert_eq!(res.termination, Termination::Gradient, "{name}");
+            assert!(res.x.iter().all(|x| x.abs() < 1e-4), "{name} {:?}", res.x);
+            assert_eq!(res.trajectory.len(), res.iterations + 1);
+        }
+    }
+
+    #[test]
+    fn test_adam_bias_correction() {
+        // the first bias corrected step moves every coordinate by the learning rate
+        let mut adam = Adam::new(0.1);
+        let mut x = vec![1., 1., 1.];
+        adam.step(&mut x, &[1e-3, -2., 50.]);
+        for (x, expected) in x.iter().zip([0.9, 1.1, 0.9]) {
+            assert!((x - expected).abs() < 1e-6, "{x}");
+        }
+    }
+
+    #[test]
+    fn test_amsgrad_keeps_maximum() {
+        let mut adam = Adam::new(0.1);
+        let mut amsgrad = Adam::new(0.1).with_amsgrad(true);
+        let (mut x, mut y) = (vec![0.], vec![0.]);
+        adam.step(&mut x, &[100.]);
+        amsgrad.step(&mut y, &[100.]);
+        assert_eq!(x, y);
+        for _ in 0..100 {
+            let (x0, y0) = (x[0], y[0]);
+            adam.step(&mut x, &[0.1]);
+            amsgrad.step(&mut y, &[0.1]);
+            assert!((y0 - y[0]).abs() <= (x0 - x[0]).abs());
+        }
+        assert!(amsgrad.v_max[0] > amsgrad.v[0]);
+    }
+
+    #[test]
+    fn test_hypergradient_adapts_learning_rate() {
+        let sgd = minimize(Sgd::new(0.001)).unwrap();
+        let mut hd = Hypergradient::new(Sgd::new(0.001), 1e-4);
+        let res = minimize(hd.clone()).unwrap();
+        assert!(res.iterations * 4 < sgd.iterations);
+
+        let mut x = vec![1., 1.];
+        for _ in 0..10 {
+            let grad = [2. * x[0], 20. * x[1]];
+            hd.step(&mut x, &grad);
+        }
+        assert!(hd.learning_rate() > 0.001);
+        hd.reset();
+        assert_eq!(hd.learning_rate(), 0.001);
+        assert_eq!(hd.inner().learning_rate(), 0.001);
+    }
+
+    #[test]
+    fn test_reset() {
+        let mut adam = Adam::default();
+        let mut x = [1., 2.];
+        adam.step(&mut x, &[1., 1.]);
+        adam.step(&mut x, &[3., -1.]);
+        adam.reset();
+
+        let mut fresh = Adam::default();
+        let (mut y, mut z) = ([1., 2.], [1., 2.]);
+        adam.step(&mut y, &[3., -1.]);
+        fresh.step(&mut z, &[3., -1.]);
+        assert_eq!((adam, y), (fresh, z));
+    }
+
+    #[test]
+    fn test_step_pairs() {
+        let mut optimizers: Vec<Box<dyn StepOptimizer>> = vec![
+            Box::new(RmsProp::new(0.01)),
+            Box::new(Adam::new(0.01)),
+            Box::new(AdaMax::new(0.01)),
+        ];
+        for optimizer in &mut optimizers {
+            let mut x = vec![1., 2., 3.];
+            let f = |x: &[f64]| (x[0] - 2.).powi(2) + (x[0] * x[1]).sin();
+            let f0 = f(&x);
+            for _ in 0..100 {
+                let tape = Tape::new();
+                let vars = x.iter().map(|&x| tape.var(x)).collect::<Vec<_>>();
+                // the last variable is unused and keeps a zero gradient
+                let loss = (&vars[0] - 2.).powi(2) + (&vars[0] * &vars[1]).sin();
+                let pairs = Node::auto_grad(Rc::new(loss));
+                assert_eq!(pairs.len(), 2);
+                optimizer.step_pairs(&mut x, &vars, &pairs);
+            }
+            assert!(f(&x) < f0);
+            assert_eq!(x[2], 3.);
+        }
+    }
+}
@@ -0,0 +1,309 @@
+use super::{dot, norm, Convergence, LineStep, OptimizeError, OptimizeResult, Optimizer, Run};
+use crate::line_search::StrongWolfe;
+use crate::objective::Objective;
+
+/// Rule for `beta` in the direction update `d_k+1 = -g_k+1 + beta d_k`, with `y = g_k+1 - g_k`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Beta {
+    /// `|g_k+1|^2 / |g_k|^2`
+    FletcherReeves,
+    /// `max(0, g_k+1 . y / |g_k|^2)`
+    #[default]
+    PolakRibierePlus,
+    /// `g_k+1 . y / d_k . y`
+    HestenesStiefel,
+    /// `|g_k+1|^2 / d_k . y`
+    DaiYuan,
+    /// `(y - 2 d_k |y|^2 / d_k . y) . g_k+1 / d_k . y`, bounded below as in CG_DESCENT.
+    HagerZhang,
+}
+
+impl Beta {
+    fn value(self, g0: &[f64], g1: &[f64], d: &[f64]) -> f64 {
+        let y = g1.iter().zip(g0).map(|(a, b)| a - b).collect::<Vec<_>>();
+        match self {
+            Self::FletcherReeves => dot(g1, g1) / dot(g0, g0),
+            Self::PolakRibierePlus => (dot(g1, &y) / dot(g0, g0)).max(0.),
+            Self::HestenesStiefel => dot(g1, &y) / dot(d, &y),
+            Self::DaiYuan => dot(g1, g1) / dot(d, &y),
+            Self::HagerZhang => {
+                let dy = dot(d, &y);
+                let beta = (dot(&y, g1) - 2. * dot(&y, &y) * dot(d, g1) / dy) / dy;
+                let eta = -1. / (norm(d) * norm(g0).min(0.01));
+                beta.max(eta)
+            }
+        }
+    }
+}
+
+/// When the direction is reset to steepest descent.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Restart {
+    /// Restart after this many iterations, `None` for the dimension of the problem.
+    pub period: Option<usize>,
+    /// Powell's criterion, restart once `|g_k+1 . g_k| >= nu |g_k+1|^2`.
+    pub powell: Option<f64>,
+}
+
+impl Default for Restart {
+    fn default() -> Self {
+        Self {
+            period: None,
+            powell: Some(0.2),
+        }
+    }
+}
+
+/// Nonlinear conjugate gradient.
+///
+/// The direction is also reset whenever the update is not a descent direction.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ConjugateGradient {
+    beta: Beta,
+    line_step: LineStep,
+    restart: Restart,
+    convergence: Convergence,
+}
+
+impl Default for ConjugateGradient {
+    fn default() -> Self {
+        Self::new(Beta::default())
+    }
+}
+
+impl ConjugateGradient {
+    /// Strong Wolfe line search with `c2 = 0.1`.
+    pub fn new(beta: Beta) -> Self {
+        Self {
+            beta,
+            line_step: LineStep::StrongWolfe(StrongWolfe {
+                c2: 0.1,
+                ..Default::default()
+            }),
+            restart: Restart::default(),
+            convergence: Convergence::default(),
+        }
+    }
+
+    pub fn with_beta(mut self, beta: Beta) -> Self {
+        self.beta = beta;
+        self
+    }
+
+    pub fn with_line_step(mut self, line_step: impl Into<LineStep>) -> Self {
+        self.line_step = line_step.into();
+        self
+    }
+
+    pub fn with_restart(mut self, restart: Restart) -> Self {
+        self.restart = restart;
+        self
+    }
+
+    pub fn with_convergence(mut self, convergence: Convergence) -> Self {
+        self.convergence = convergence;
+        self
+    }
+
+    fn check(&self) -> Result<(), OptimizeError> {
+        match self.restart {
+            Restart {
+                period: Some(0), ..
+            } => Err(OptimizeError::InvalidParameter {
+                name: "period",
+                value: 0.,
+            }),
+            Restart {
+                powell: Some(nu), ..
+            } if nu <= 0. || nu.is_nan() => Err(OptimizeError::InvalidParameter {
+                name: "powell",
+                value: nu,
+            }),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl Optimizer for ConjugateGradient {
+    fn minimize(
+        &self,
+        objective: &dyn Objective,
+        x0: &[f64],
+    ) -> Result<OptimizeResult, OptimizeError> {
+        self.check()?;
+        let mut run = Run::start(objective, x0, self.convergence)?;
+        let period = self.restart.period.unwrap_or(x0.len()).max(1);
+
+        let mut d = run.grad().iter().map(|g| -g).collect::<Vec<_>>();
+        let mut since_restart = 0;
+        loop {
+            if let Some(termination) = run.termination() {
+                return Ok(run.finish(termination));
+            }
+            let g0 = run.grad().to_vec();
+            run.line_step(objective, &self.line_step, &d)?;
+            since_restart += 1;
+
+            let g1 = run.grad();
+            let beta = self.beta.value(&g0, g1, &d);
+            let restart = since_restart >= period
+                || !beta.is_finite()
+                || self
+                    .restart
+                    .powell
+                    .is_some_and(|nu| dot(g1, &g0).abs() >= nu * dot(g1, g1));
+            let beta = if restart { 0. } else { beta };
+            d.iter_mut().zip(g1).for_each(|(d, g)| *d = beta * *d - g);
+            if restart || dot(&d, g1) >= 0. {
+                d.iter_mut().zip(g1).for_each(|(d, g)| *d = -g);
+                since_restart = 0;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bracketing::{LineMinimizer, Tolerance};
+    use crate::line_search::{Backtracking, HagerZhang};
+    use crate::objective::FnObjective;
+    use crate::optimizer::{GradientDescent, StepSize, Termination};
+    use crate::test_math_funcs::rosenbrock_objective;
+
+    const BETAS: [Beta; 5] = [
+        Beta::FletcherReeves,
+        Beta::PolakRibierePlus,
+        Beta::HestenesStiefel,
+        Beta::DaiYuan,
+        Beta::HagerZhang,
+    ];
+
+    /// `sum i^2 x_i^2 / 2`, condition number `n^2`.
+    fn ill_conditioned(n: usize) -> FnObjective {
+        FnObjective::new(n, |x| {
+            x.iter()
+                .enumerate()
+                .map(|(i, x)| ((i + 1) * (i + 1)) as f64 * x * x / 2.)
+                .sum()
+        })
+        .with_gradient(|x, g| {
+            g.iter_mut()
+                .zip(x)
+                .enumerate()
+                .for_each(|(i, (g, x))| *g = ((i + 1) * (i + 1)) as f64 * x)
+        })
+    }
+
+    fn exact() -> LineMinimizer {
+        LineMinimizer::new()
+            .with_step(1e-8)
+            .with_tolerance(Tolerance::Relative(1e-10))
+    }
+
+    #[test]
+    fn test_betas_on_rosenbrock() {
+        for beta in BETAS {
+            let res = ConjugateGradient::new(beta)
+                .minimize(&rosenbrock_objective(1., 100.), &[-1.2, 1.])
+                .unwrap();
+            assert_eq!(res.termination, Termination::Gradient, "{beta:?}");
+            assert!((res.x[0] - 1.).abs() < 1e-5, "{beta:?} {:?}", res.x);
+            assert!((res.x[1] - 1.).abs() < 1e-5, "{beta:?} {:?}", res.x);
+        }
+    }
+
+    #[test]
+    fn test_line_steps_on_rosenbrock() {
+        for line_step in [
+            LineStep::Exact(exact()),
+            LineStep::Backtracking(Backtracking::default()),
+            LineStep::StrongWolfe(StrongWolfe::default()),
+            LineStep::HagerZhang(HagerZhang::default()),
+        ] {
+            let res = ConjugateGradient::default()
+                .with_line_step(line_step)
+                .with_convergence(Convergence {
+                    max_iter: 10000,
+                    ..Default::default()
+                })
+                .minimize(&rosenbrock_objective(1., 100.), &[-1.2, 1.])
+                .unwrap();
+            assert!(res.converged(), "{line_step:?}");
+            assert!((res.x[0] - 1.).abs() < 1e-4, "{line_step:?} {:?}", res.x);
+        }
+    }
+
+    #[test]
+    fn test_ill_conditioned_quadratic() {
+        let n = 10;
+        let objective = ill_conditioned(n);
+        let x0 = vec![1.; n];
+        let convergence = Convergence {
+            gradient_tol: 1e-8,
+            max_iter: 10000,
+            ..Default::default()
+        };
+        let gd = GradientDescent::new(StepSize::Exact(exact()))
+            .with_convergence(convergence)
+            .minimize(&objective, &x0)
+            .unwrap();
+        for beta in BETAS {
+            let res = ConjugateGradient::new(beta)
+                .with_line_step(exact())
+                .with_restart(Restart {
+                    period: None,
+                    powell: None,
+                })
+                .with_convergence(convergence)
+                .minimize(&objective, &x0)
+                .unwrap();
+            assert_eq!(res.termination, Termination::Gradient, "{beta:?}");
+            assert!(res.iterations <= 3 * n, "{beta:?} {}", res.iterations);
+            assert!(
+                res.iterations * 10 < gd.iterations,
+                "{beta:?} {} {}",
+                res.iterations,
+                gd.iterations
+            );
+        }
+    }
+
+    #[test]
+    fn test_restart_every_iteration_is_steepest_descent() {
+        let objective = rosenbrock_objective(1., 100.);
+        let convergence = Convergence {
+            max_iter: 20,
+            ..Default::default()
+        };
+        let cg = ConjugateGradient::default()
+            .with_line_step(exact())
+            .with_restart(Restart {
+                period: Some(1),
+                powell: None,
+            })
+            .with_convergence(convergence)
+            .minimize(&objective, &[-1.2, 1.])
+            .unwrap();
+        let gd = GradientDescent::new(StepSize::Exact(exact()))
+            .with_convergence(convergence)
+            .minimize(&objective, &[-1.2, 1.])
+            .unwrap();
+        assert_eq!(cg.trajectory, gd.trajectory);
+    }
+
+    #[test]
+    fn test_invalid_restart() {
+        let err = ConjugateGradient::default()
+            .with_restart(Restart {
+                period: Some(0),
+                powell: None,
+            })
+            .minimize(&rosenbrock_objective(1., 100.), &[-1.2, 1.])
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            OptimizeError::InvalidParameter { name: "period", .. }
+        ));
+    }
+}
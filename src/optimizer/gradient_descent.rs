@@ -0,0 +1,257 @@
+use super::{exact_step, Convergence, OptimizeError, OptimizeResult, Optimizer, Run};
+use crate::bracketing::LineMinimizer;
+use crate::line_search::{Backtracking, LineSearch};
+use crate::objective::{LineFunction, Objective};
+
+/// Step length along the negative gradient.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StepSize {
+    /// `x_k+1 = x_k - alpha grad f(x_k)`
+    Fixed(f64),
+    /// `alpha0 / (1 + decay k)` at iteration `k`.
+    Decaying { alpha0: f64, decay: f64 },
+    /// Minimizes `f` along the negative gradient.
+    Exact(LineMinimizer),
+    /// Backtracks from `alpha0` until the Armijo condition holds.
+    Armijo(Backtracking),
+}
+
+impl StepSize {
+    pub(super) fn check(&self) -> Result<(), OptimizeError> {
+        let invalid = |name, value| Err(OptimizeError::InvalidParameter { name, value });
+        match *self {
+            Self::Fixed(alpha) if !(alpha > 0. && alpha.is_finite()) => invalid("alpha", alpha),
+            Self::Decaying { alpha0, .. } if !(alpha0 > 0. && alpha0.is_finite()) => {
+                invalid("alpha0", alpha0)
+            }
+            Self::Decaying { decay, .. } if !(decay >= 0. && decay.is_finite()) => {
+                invalid("decay", decay)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Step length along `line` after `iteration` iterations.
+    pub(super) fn length(
+        &self,
+        line: &LineFunction,
+        iteration: usize,
+    ) -> Result<f64, OptimizeError> {
+        match self {
+            Self::Fixed(alpha) => Ok(*alpha),
+            Self::Decaying { alpha0, decay } => Ok(alpha0 / (1. + decay * iteration as f64)),
+            Self::Exact(minimizer) => exact_step(line, minimizer, iteration + 1),
+            Self::Armijo(search) => {
+                search
+                    .search_line(line)
+                    .map(|res| res.alpha)
+                    .map_err(|error| OptimizeError::LineSearch {
+                        iteration: iteration + 1,
+                        error,
+                    })
+            }
+        }
+    }
+}
+
+/// Steepest descent, moving along `-grad f(x)` with the chosen step size.
+///
+/// ```
+/// use optimize_examples::objective::FnObjective;
+/// use optimize_examples::optimizer::{GradientDescent, Optimizer, StepSize};
+///
+/// let objective = FnObjective::new(2, |x| x[0] * x[0] + 4. * x[1] * x[1]);
+/// let res = GradientDescent::new(StepSize::Fixed(0.1))
+///     .minimize(&objective, &[1., 1.])
+///     .unwrap();
+/// assert!(res.converged() && res.x[0].abs() < 1e-5);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GradientDescent {
+    step_size: StepSize,
+    convergence: Convergence,
+}
+
+impl Default for GradientDescent {
+    fn default() -> Self {
+        Self::new(StepSize::Armijo(Backtracking::default()))
+    }
+}
+
+impl GradientDescent {
+    pub fn new(step_size: StepSize) -> Self {
+        Self {
+            step_size,
+            convergence: Convergence::default(),
+        }
+    }
+
+    pub fn with_step_size(mut self, step_size: StepSize) -> Self {
+        self.step_size = step_size;
+        self
+    }
+
+    pub fn with_convergence(mut self, convergence: Convergence) -> Self {
+        self.convergence = convergence;
+        self
+    }
+}
+
+impl Optimizer for GradientDescent {
+    fn minimize(
+        &self,
+        objective: &dyn Objective,
+        x0: &[f64],
+    ) -> Result<OptimizeResult, OptimizeError> {
+        self.step_size.check()?;
+        let mut run = Run::start(objective, x0, self.convergence)?;
+
+        loop {
+            if let Some(termination) = run.termination() {
+                return Ok(run.finish(termination));
+            }
+            let d = run.grad().iter().map(|g| -g).collect::<Vec<_>>();
+
+            match &self.step_size {
+                StepSize::Fixed(_) | StepSize::Decaying { .. } => {
+                    let line = LineFunction::new(objective, run.x(), &d);
+                    let alpha = self.step_size.length(&line, run.iteration())?;
+                    run.push_point(objective, line.point(alpha))?;
+                }
+                StepSize::Exact(minimizer) => run.line_minimize(objective, minimizer, &d)?,
+                StepSize::Armijo(search) => run.line_search(objective, search, &d)?,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::objective::FnObjective;
+    use crate::optimizer::Termination;
+    use crate::test_math_funcs::rosenbrock_objective;
+
+    fn quadratic() -> FnObjective {
+        FnObjective::new(2, |x| x[0] * x[0] + 4. * x[1] * x[1])
+            .with_gradient(|x, g| g.copy_from_slice(&[2. * x[0], 8. * x[1]]))
+    }
+
+    #[test]
+    fn test_step_sizes_on_quadratic() {
+        for step_size in [
+            StepSize::Fixed(0.1),
+            StepSize::Decaying {
+                alpha0: 0.2,
+                decay: 0.01,
+            },
+            StepSize::Exact(LineMinimizer::new().with_step(1e-8)),
+            StepSize::Armijo(Backtracking::default()),
+        ] {
+            let res = GradientDescent::new(step_size)
+                .minimize(&quadratic(), &[1., 1.])
+                .unwrap();
+            assert_eq!(res.termination, Termination::Gradient, "{step_size:?}");
+            assert!(res.x.iter().all(|x| x.abs() < 1e-6), "{step_size:?}");
+            assert_eq!(res.trajectory.len(), res.iterations + 1);
+            assert_eq!(res.values.len(), res.iterations + 1);
+            assert_eq!(res.trajectory[0], vec![1., 1.]);
+        }
+    }
+
+    #[test]
+    fn test_rosenbrock_armijo() {
+        let res = GradientDescent::default()
+            .with_convergence(Convergence {
+                gradient_tol: 1e-4,
+                max_iter: 50000,
+                ..Default::default()
+            })
+            .minimize(&rosenbrock_objective(1., 100.), &[-1.2, 1.])
+            .unwrap();
+        assert!(res.converged());
+        assert!(res.values.windows(2).all(|v| v[1] <= v[0]));
+        assert!((res.x[0] - 1.).abs() < 1e-2 && (res.x[1] - 1.).abs() < 1e-2);
+    }
+
+    #[test]
+    fn test_termination() {
+        let gd = GradientDescent::new(StepSize::Fixed(0.01));
+        let res = gd
+            .with_convergence(Convergence {
+                max_iter: 5,
+                ..Default::default()
+            })
+            .minimize(&quadratic(), &[1., 1.])
+            .unwrap();
+        assert_eq!(res.termination, Termination::MaxIterations);
+        assert_eq!(res.iterations, 5);
+        assert!(!res.converged());
+
+        let res = gd
+            .with_convergence(Convergence {
+                value_tol: 1e-3,
+                ..Default::default()
+            })
+            .minimize(&quadratic(), &[1., 1.])
+            .unwrap();
+        assert_eq!(res.termination, Termination::Value);
+
+        let res = gd
+            .with_convergence(Convergence {
+                step_tol: 1e-3,
+                ..Default::default()
+            })
+            .minimize(&quadratic(), &[1., 1.])
+            .unwrap();
+        assert_eq!(res.termination, Termination::Step);
+
+        // the same run without the trajectory
+        let short = gd
+            .with_convergence(Convergence {
+                step_tol: 1e-3,
+                keep_trajectory: false,
+                ..Default::default()
+            })
+            .minimize(&quadratic(), &[1., 1.])
+            .unwrap();
+        assert_eq!(
+            (short.termination, short.iterations, &short.x),
+            (res.termination, res.iterations, &res.x)
+        );
+        assert_eq!(short.trajectory.len(), 1);
+
+        let res = gd.minimize(&quadratic(), &[0., 0.]).unwrap();
+        assert_eq!(
+            (res.termination, res.iterations),
+            (Termination::Gradient, 0)
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        let err = GradientDescent::new(StepSize::Fixed(1.))
+            .minimize(&quadratic(), &[1., 1.])
+            .unwrap_err();
+        assert!(matches!(err, OptimizeError::NonFinite { .. }), "{err}");
+
+        let err = GradientDescent::new(StepSize::Fixed(0.))
+            .minimize(&quadratic(), &[1., 1.])
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            OptimizeError::InvalidParameter { name: "alpha", .. }
+        ));
+
+        let err = GradientDescent::default()
+            .minimize(&quadratic(), &[1.])
+            .unwrap_err();
+        assert_eq!(
+            err,
+            OptimizeError::Dimension {
+                expected: 2,
+                found: 1
+            }
+        );
+    }
+}
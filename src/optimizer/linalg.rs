@@ -0,0 +1,167 @@
+//! Dense symmetric `n x n` matrices, stored row-major in slices of length `n * n`.
+
+/// Gill-Murray modified Cholesky factorization `L D L^T = A + E`, with `L` unit lower
+/// triangular, `D` positive and `E` a non-negative diagonal that is zero when `A` is
+/// sufficiently positive definite.
+///
+/// Returns `L` row-major and the diagonal of `D`.
+pub(super) fn modified_cholesky(a: &[f64], n: usize) -> (Vec<f64>, Vec<f64>) {
+    let gamma = (0..n).map(|i| a[i * n + i].abs()).fold(0., f64::max);
+    let xi = (0..n)
+        .flat_map(|i| (0..n).filter(move |&j| j != i).map(move |j| (i, j)))
+        .map(|(i, j)| a[i * n + j].abs())
+        .fold(0., f64::max);
+    let nu = ((n * n) as f64 - 1.).sqrt().max(1.);
+    let beta2 = gamma.max(xi / nu).max(f64::EPSILON);
+    let delta = f64::EPSILON * (gamma + xi).max(1.);
+
+    let mut l = vec![0.; n * n];
+    let mut d = vec![0.; n];
+    let mut c = vec![0.; n];
+    for j in 0..n {
+        l[j * n + j] = 1.;
+        for (i, c) in c.iter_mut().enumerate().skip(j) {
+            *c = a[i * n + j]
+                - (0..j)
+                    .map(|s| d[s] * l[i * n + s] * l[j * n + s])
+                    .sum::<f64>();
+        }
+        let theta = c[j + 1..].iter().fold(0., |m: f64, c| m.max(c.abs()));
+        d[j] = c[j].abs().max(theta * theta / beta2).max(delta);
+        for i in j + 1..n {
+            l[i * n + j] = c[i] / d[j];
+        }
+    }
+    (l, d)
+}
+
+/// Solves `L D L^T x = b` for the factors of `modified_cholesky`.
+pub(super) fn ldl_solve(l: &[f64], d: &[f64], b: &[f64]) -> Vec<f64> {
+    let n = d.len();
+    let mut x = b.to_vec();
+    for i in 0..n {
+        x[i] -= (0..i).map(|j| l[i * n + j] * x[j]).sum::<f64>();
+    }
+    x.iter_mut().zip(d).for_each(|(x, d)| *x /= d);
+    for i in (0..n).rev() {
+        x[i] -= (i + 1..n).map(|j| l[j * n + i] * x[j]).sum::<f64>();
+    }
+    x
+}
+
+/// Eigenvalues and eigenvectors of a symmetric matrix by cyclic Jacobi rotations.
+///
+/// Column `i` of the returned row-major matrix is the eigenvector of eigenvalue `i`.
+pub(super) fn symmetric_eigen(a: &[f64], n: usize) -> (Vec<f64>, Vec<f64>) {
+    let mut a = a.to_vec();
+    let mut v = vec![0.; n * n];
+    (0..n).for_each(|i| v[i * n + i] = 1.);
+
+    let norm = a.iter().map(|a| a * a).sum::<f64>().sqrt();
+    for _ in 0..100 {
+        let off = (0..n)
+            .flat_map(|p| (p + 1..n).map(move |q| (p, q)))
+            .map(|(p, q)| a[p * n + q] * a[p * n + q])
+            .sum::<f64>();
+        if off.sqrt() <= f64::EPSILON * norm {
+            break;
+        }
+        for p in 0..n {
+            for q in p + 1..n {
+                if a[p * n + q] == 0. {
+                    continue;
+                }
+                let theta = (a[q * n + q] - a[p * n + p]) / (2. * a[p * n + q]);
+                let t = theta.signum() / (theta.abs() + theta.hypot(1.));
+                let c = 1. / t.hypot(1.);
+                let s = t * c;
+                for k in 0..n {
+                    let (apk, aqk) = (a[p * n + k], a[q * n + k]);
+                    a[p * n + k] = c * apk - s * aqk;
+                    a[q * n + k] = s * apk + c * aqk;
+                }
+                for m in [&mut a, &mut v] {
+                    for k in 0..n {
+                        let (mkp, mkq) = (m[k * n + p], m[k * n + q]);
+                        m[k * n + p] = c * mkp - s * mkq;
+                        m[k * n + q] = s * mkp + c * mkq;
+                    }
+                }
+            }
+        }
+    }
+    ((0..n).map(|i| a[i * n + i]).collect(), v)
+}
+
+pub(super) fn mat_vec(a: &[f64], x: &[f64]) -> Vec<f64> {
+    a.chunks(x.len())
+        .map(|row| row.iter().zip(x).map(|(a, x)| a * x).sum())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ldl(l: &[f64], d: &[f64]) -> Vec<f64> {
+        let n = d.len();
+        (0..n * n)
+            .map(|ij| {
+                let (i, j) = (ij / n, ij % n);
+                (0..n).map(|s| l[i * n + s] * d[s] * l[j * n + s]).sum()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_modified_cholesky() {
+        let pd = [4., 2., 0.4, 2., 5., 1., 0.4, 1., 3.];
+        let (l, d) = modified_cholesky(&pd, 3);
+        for (a, b) in ldl(&l, &d).iter().zip(pd) {
+            assert!((a - b).abs() < 1e-12);
+        }
+        for (x, expected) in ldl_solve(&l, &d, &mat_vec(&pd, &[1., -2., 3.]))
+            .iter()
+            .zip([1., -2., 3.])
+        {
+            assert!((x - expected).abs() < 1e-12);
+        }
+
+        // indefinite, only the diagonal is modified and the result is positive definite
+        let indefinite = [1., 2., 3., 2., -4., 1., 3., 1., 0.];
+        let (l, d) = modified_cholesky(&indefinite, 3);
+        assert!(d.iter().all(|&d| d > 0.));
+        let e = ldl(&l, &d)
+            .iter()
+            .zip(indefinite)
+            .map(|(a, b)| a - b)
+            .collect::<Vec<_>>();
+        for i in 0..3 {
+            for j in 0..3 {
+                if i == j {
+                    assert!(e[i * 3 + j] >= -1e-12);
+                } else {
+                    assert!(e[i * 3 + j].abs() < 1e-12);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_symmetric_eigen() {
+        let a = [2., -1., 0., -1., 2., -1., 0., -1., 2.];
+        let (lambda, v) = symmetric_eigen(&a, 3);
+        let mut sorted = lambda.clone();
+        sorted.sort_by(f64::total_cmp);
+        let r = std::f64::consts::SQRT_2;
+        for (l, expected) in sorted.iter().zip([2. - r, 2., 2. + r]) {
+            assert!((l - expected).abs() < 1e-12);
+        }
+        for (i, lambda) in lambda.iter().enumerate() {
+            let vi = (0..3).map(|k| v[k * 3 + i]).collect::<Vec<_>>();
+            for (av, v) in mat_vec(&a, &vi).iter().zip(&vi) {
+                assert!((av - lambda * v).abs() < 1e-12);
+            }
+        }
+    }
+}
@@ -0,0 +1,221 @@
+use super::{Convergence, OptimizeError, OptimizeResult, Optimizer, Run, StepSize};
+use crate::objective::{LineFunction, Objective};
+
+/// Heavy ball momentum, `v_k+1 = decay v_k - alpha grad f(x_k)` and `x_k+1 = x_k + v_k+1`.
+///
+/// Line search step sizes search along `-grad f(x_k)` from `x_k`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Momentum {
+    step_size: StepSize,
+    decay: f64,
+    convergence: Convergence,
+}
+
+/// Nesterov accelerated gradient, momentum with the gradient taken at the look ahead point
+/// `y_k = x_k + decay v_k`.
+///
+/// Line search step sizes search along `-grad f(y_k)` from `y_k`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NesterovMomentum {
+    step_size: StepSize,
+    decay: f64,
+    convergence: Convergence,
+}
+
+macro_rules! impl_momentum {
+    ($t:ident, $nesterov:literal) => {
+        impl $t {
+            pub fn new(step_size: StepSize, decay: f64) -> Self {
+                Self {
+                    step_size,
+                    decay,
+                    convergence: Convergence::default(),
+                }
+            }
+
+            pub fn with_step_size(mut self, step_size: StepSize) -> Self {
+                self.step_size = step_size;
+                self
+            }
+
+            pub fn with_decay(mut self, decay: f64) -> Self {
+                self.decay = decay;
+                self
+            }
+
+            pub fn with_convergence(mut self, convergence: Convergence) -> Self {
+                self.convergence = convergence;
+                self
+            }
+        }
+
+        impl Optimizer for $t {
+            fn minimize(
+                &self,
+                objective: &dyn Objective,
+                x0: &[f64],
+            ) -> Result<OptimizeResult, OptimizeError> {
+                accelerate(
+                    objective,
+                    x0,
+                    &self.step_size,
+                    self.decay,
+                    self.convergence,
+                    $nesterov,
+                )
+            }
+        }
+    };
+}
+
+impl_momentum!(Momentum, false);
+impl_momentum!(NesterovMomentum, true);
+
+fn accelerate(
+    objective: &dyn Objective,
+    x0: &[f64],
+    step_size: &StepSize,
+    decay: f64,
+    convergence: Convergence,
+    nesterov: bool,
+) -> Result<OptimizeResult, OptimizeError> {
+    step_size.check()?;
+    if !(0. ..1.).contains(&decay) {
+        return Err(OptimizeError::InvalidParameter {
+            name: "decay",
+            value: decay,
+        });
+    }
+    let mut run = Run::start(objective, x0, convergence)?;
+
+    let mut v = vec![0.; x0.len()];
+    loop {
+        if let Some(termination) = run.termination() {
+            return Ok(run.finish(termination));
+        }
+        let line = if nesterov {
+            let y = run
+                .x()
+                .iter()
+                .zip(&v)
+                .map(|(x, v)| x + decay * v)
+                .collect::<Vec<_>>();
+            let mut grad = vec![0.; y.len()];
+            let fy = objective.value_and_gradient(&y, &mut grad);
+            let d = grad.iter().map(|g| -g).collect::<Vec<_>>();
+            LineFunction::new(objective, &y, &d).with_start(fy, &grad)
+        } else {
+            let d = run.grad().iter().map(|g| -g).collect::<Vec<_>>();
+            LineFunction::new(objective, run.x(), &d).with_start(run.fx(), run.grad())
+        };
+        let alpha = step_size.length(&line, run.iteration())?;
+
+        v.iter_mut()
+            .zip(line.direction())
+            .for_each(|(v, d)| *v = decay * *v + alpha * d);
+        let x = run.x().iter().zip(&v).map(|(x, v)| x + v).collect();
+        run.push_point(objective, x)?;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bracketing::LineMinimizer;
+    use crate::objective::FnObjective;
+    use crate::optimizer::{GradientDescent, Termination};
+    use crate::test_math_funcs::rosenbrock_objective;
+
+    /// Hessian eigenvalues 2 and 50.
+    fn quadratic() -> FnObjective {
+        FnObjective::new(2, |x| x[0] * x[0] + 25. * x[1] * x[1])
+            .with_gradient(|x, g| g.copy_from_slice(&[2. * x[0], 50. * x[1]]))
+    }
+
+    #[test]
+    fn test_acceleration_on_quadratic() {
+        let step_size = StepSize::Fixed(0.02);
+        let gd = GradientDescent::new(step_size)
+            .minimize(&quadratic(), &[1., 1.])
+            .unwrap();
+        let momentum = Momentum::new(step_size, 0.7)
+            .minimize(&quadratic(), &[1., 1.])
+            .unwrap();
+        let nesterov = NesterovMomentum::new(step_size, 0.7)
+            .minimize(&quadratic(), &[1., 1.])
+            .unwrap();
+        for res in [&gd, &momentum, &nesterov] {
+            assert_eq!(res.termination, Termination::Gradient);
+            assert!(res.x.iter().all(|x| x.abs() < 1e-6));
+            assert_eq!(res.trajectory.len(), res.iterations + 1);
+        }
+        assert!(momentum.iterations * 2 < gd.iterations);
+        assert!(nesterov.iterations * 2 < gd.iterations);
+    }
+
+    #[test]
+    fn test_zero_decay_is_gradient_descent() {
+        let step_size = StepSize::Decaying {
+            alpha0: 0.03,
+            decay: 0.1,
+        };
+        let convergence = Convergence {
+            max_iter: 20,
+            ..Default::default()
+        };
+        let gd = GradientDescent::new(step_size)
+            .with_convergence(convergence)
+            .minimize(&quadratic(), &[1., 1.])
+            .unwrap();
+        for res in [
+            Momentum::new(step_size, 0.).with_convergence(convergence),
+            Momentum::new(step_size, 0.9)
+                .with_decay(0.)
+                .with_convergence(convergence),
+        ]
+        .map(|m| m.minimize(&quadratic(), &[1., 1.]).unwrap())
+        {
+            assert_eq!(res.trajectory, gd.trajectory);
+        }
+        let res = NesterovMomentum::new(step_size, 0.)
+            .with_convergence(convergence)
+            .minimize(&quadratic(), &[1., 1.])
+            .unwrap();
+        assert_eq!(res.trajectory, gd.trajectory);
+    }
+
+    #[test]
+    fn test_line_search_steps_on_rosenbrock() {
+        let step_size = StepSize::Exact(LineMinimizer::new().with_step(1e-8));
+        let convergence = Convergence {
+            max_iter: 30,
+            ..Default::default()
+        };
+        let objective = rosenbrock_objective(1., 100.);
+        let momentum = Momentum::new(step_size, 0.7)
+            .with_convergence(convergence)
+            .minimize(&objective, &[-1.4, 1.1])
+            .unwrap();
+        let nesterov = NesterovMomentum::new(step_size, 0.7)
+            .with_convergence(convergence)
+            .minimize(&objective, &[-1.4, 1.1])
+            .unwrap();
+        for res in [momentum, nesterov] {
+            assert_eq!(res.values.len(), 31);
+            assert!(res.fx < res.values[0] / 2.);
+        }
+    }
+
+    #[test]
+    fn test_invalid_decay() {
+        for decay in [-0.1, 1., f64::NAN] {
+            let err = Momentum::new(StepSize::Fixed(0.01), decay)
+                .minimize(&quadratic(), &[1., 1.])
+                .unwrap_err();
+            assert!(matches!(
+                err,
+                OptimizeError::InvalidParameter { name: "decay", .. }
+            ));
+        }
+    }
+}
@@ -0,0 +1,226 @@
+use super::linalg::{ldl_solve, modified_cholesky, symmetric_eigen};
+use super::{dot, Convergence, LineStep, OptimizeError, OptimizeResult, Optimizer, Run};
+use crate::line_search::Backtracking;
+use crate::objective::Objective;
+
+/// Change made to an indefinite Hessian so the Newton direction is a descent direction.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Modification {
+    /// Gill-Murray modified Cholesky, increases the diagonal while factorizing.
+    #[default]
+    ModifiedCholesky,
+    /// Adds `tau I`, the smallest `tau >= 0` that moves every eigenvalue to at least `delta`.
+    EigenvalueShift { delta: f64 },
+}
+
+impl Modification {
+    /// Solves `(H + E) d = -grad` for the modified Hessian.
+    fn direction(self, hess: &[f64], grad: &[f64]) -> Vec<f64> {
+        let n = grad.len();
+        let neg_grad = grad.iter().map(|g| -g).collect::<Vec<_>>();
+        match self {
+            Self::ModifiedCholesky => {
+                let (l, d) = modified_cholesky(hess, n);
+                ldl_solve(&l, &d, &neg_grad)
+            }
+            Self::EigenvalueShift { delta } => {
+                let (lambda, v) = symmetric_eigen(hess, n);
+                let tau = (delta - lambda.iter().fold(f64::INFINITY, |m, l| m.min(*l))).max(0.);
+                let mut d = vec![0.; n];
+                for (i, lambda) in lambda.iter().enumerate() {
+                    let vi = (0..n).map(|k| v[k * n + i]).collect::<Vec<_>>();
+                    let c = dot(&vi, &neg_grad) / (lambda + tau);
+                    d.iter_mut().zip(&vi).for_each(|(d, v)| *d += c * v);
+                }
+                d
+            }
+        }
+    }
+}
+
+/// Newton's method with a line search along the direction of the modified Newton system
+/// `(H + E) d = -grad f(x)`.
+///
+/// The Hessian comes from `Objective::hessian`, so it is exact for objectives built with
+/// `FnObjective::with_hessian` or `GraphObjective` and approximated for `FiniteDiff`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Newton {
+    modification: Modification,
+    line_step: LineStep,
+    convergence: Convergence,
+}
+
+impl Default for Newton {
+    fn default() -> Self {
+        Self::new(Modification::default())
+    }
+}
+
+impl Newton {
+    /// Backtracking from the full Newton step.
+    pub fn new(modification: Modification) -> Self {
+        Self {
+            modification,
+            line_step: LineStep::Backtracking(Backtracking::default()),
+            convergence: Convergence::default(),
+        }
+    }
+
+    pub fn with_modification(mut self, modification: Modification) -> Self {
+        self.modification = modification;
+        self
+    }
+
+    pub fn with_line_step(mut self, line_step: impl Into<LineStep>) -> Self {
+        self.line_step = line_step.into();
+        self
+    }
+
+    pub fn with_convergence(mut self, convergence: Convergence) -> Self {
+        self.convergence = convergence;
+        self
+    }
+}
+
+impl Optimizer for Newton {
+    fn minimize(
+        &self,
+        objective: &dyn Objective,
+        x0: &[f64],
+    ) -> Result<OptimizeResult, OptimizeError> {
+        if let Modification::EigenvalueShift { delta } = self.modification {
+            if delta <= 0. || !delta.is_finite() {
+                return Err(OptimizeError::InvalidParameter {
+                    name: "delta",
+                    value: delta,
+                });
+            }
+        }
+        let mut run = Run::start(objective, x0, self.convergence)?;
+        let n = x0.len();
+
+        let mut hess = vec![0.; n * n];
+        loop {
+            if let Some(termination) = run.termination() {
+                return Ok(run.finish(termination));
+            }
+            objective.hessian(run.x(), &mut hess);
+            if hess.iter().any(|h| !h.is_finite()) {
+                return Err(OptimizeError::NonFinite {
+                    iteration: run.iteration(),
+                    x: run.x().to_vec(),
+                });
+            }
+            let d = self.modification.direction(&hess, run.grad());
+            run.line_step(objective, &self.line_step, &d)?;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::autograd::compute_graph::node::Node;
+    use crate::objective::{FiniteDiff, GraphObjective};
+    use crate::optimizer::Termination;
+    use crate::test_math_funcs::{rosenbrock, rosenbrock_objective};
+
+    const STARTS: [[f64; 2]; 3] = [[-1.4, 0.9], [-1.4, 1.], [-1.4, 1.1]];
+
+    fn errors(res: &OptimizeResult) -> Vec<f64> {
+        res.trajectory
+            .iter()
+            .map(|x| (x[0] - 1.).hypot(x[1] - 1.))
+            .collect()
+    }
+
+    #[test]
+    fn test_quadratic_convergence_on_rosenbrock() {
+        for modification in [
+            Modification::ModifiedCholesky,
+            Modification::EigenvalueShift { delta: 1e-8 },
+        ] {
+            for x0 in STARTS {
+                let res = Newton::new(modification)
+                    .with_convergence(Convergence {
+                        gradient_tol: 1e-10,
+                        ..Default::default()
+                    })
+                    .minimize(&rosenbrock_objective(1., 100.), &x0)
+                    .unwrap();
+                assert_eq!(res.termination, Termination::Gradient);
+                assert!(res.iterations < 30, "{x0:?} {}", res.iterations);
+
+                // once close, the error is squared at every step
+                let e = errors(&res);
+                let close = e
+                    .windows(2)
+                    .filter(|e| e[0] < 2e-2 && e[1] > 1e-12)
+                    .collect::<Vec<_>>();
+                assert!(close.len() >= 2, "{x0:?} {e:?}");
+                for e in close {
+                    assert!(e[1] <= 100. * e[0] * e[0], "{x0:?} {e:?}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_hessian_sources_agree() {
+        let graph = GraphObjective::new(2, |x: &[Node]| {
+            let t = &x[1] - &x[0] * &x[0];
+            (1. - &x[0]) * (1. - &x[0]) + 100. * &t * &t
+        });
+        let finite_diff = FiniteDiff::new(rosenbrock_objective(1., 100.));
+        let exact = Newton::default()
+            .minimize(&rosenbrock_objective(1., 100.), &STARTS[0])
+            .unwrap();
+        let res = Newton::default().minimize(&graph, &STARTS[0]).unwrap();
+        assert_eq!(res.iterations, exact.iterations);
+        assert!(res.x.iter().all(|x| (x - 1.).abs() < 1e-6));
+
+        let res = Newton::default()
+            .with_convergence(Convergence {
+                gradient_tol: 1e-5,
+                ..Default::default()
+            })
+            .minimize(&finite_diff, &STARTS[0])
+            .unwrap();
+        assert!(res.converged());
+        assert!(res.x.iter().all(|x| (x - 1.).abs() < 1e-4), "{:?}", res.x);
+    }
+
+    #[test]
+    fn test_indefinite_hessian() {
+        // the Hessian at (0, 1) has eigenvalues -398 and 200
+        let objective = rosenbrock_objective(1., 100.);
+        for modification in [
+            Modification::ModifiedCholesky,
+            Modification::EigenvalueShift { delta: 1e-8 },
+        ] {
+            let mut hess = vec![0.; 4];
+            let mut grad = vec![0.; 2];
+            objective.hessian(&[0., 1.], &mut hess);
+            objective.gradient(&[0., 1.], &mut grad);
+            assert!(dot(&modification.direction(&hess, &grad), &grad) < 0.);
+
+            let res = Newton::new(modification)
+                .minimize(&objective, &[0., 1.])
+                .unwrap();
+            assert!(res.converged());
+            assert!(res.values.windows(2).all(|v| v[1] <= v[0]));
+            assert!(rosenbrock(&[res.x[0], res.x[1]], 1., 100.) < 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_invalid_shift() {
+        let err = Newton::new(Modification::EigenvalueShift { delta: 0. })
+            .minimize(&rosenbrock_objective(1., 100.), &STARTS[0])
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            OptimizeError::InvalidParameter { name: "delta", .. }
+        ));
+    }
+}
@@ -0,0 +1,521 @@
+use std::collections::VecDeque;
+
+use super::linalg::mat_vec;
+use super::{dot, norm, Convergence, LineStep, OptimizeError, OptimizeResult, Optimizer, Run};
+use crate::line_search::StrongWolfe;
+use crate::objective::Objective;
+
+/// What happens to an update when the curvature condition `s . y > 0` fails, with
+/// `s = x_k+1 - x_k` and `y = grad f(x_k+1) - grad f(x_k)`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CurvatureGuard {
+    /// Keeps the previous approximation.
+    #[default]
+    Skip,
+    /// Powell damping, replaces `y` by `r = theta y + (1 - theta) B s` so that
+    /// `s . r >= 0.2 s . B s`, also when the condition holds only barely.
+    Damp,
+}
+
+impl CurvatureGuard {
+    /// `y` or its replacement, `None` to skip the update.
+    ///
+    /// `bs` is `B s` for the approximate Hessian `B` the step was taken with.
+    fn pair(self, s: &[f64], y: Vec<f64>, bs: &[f64]) -> Option<Vec<f64>> {
+        let sy = dot(s, &y);
+        match self {
+            Self::Skip => (sy > f64::EPSILON * norm(s) * norm(&y)).then_some(y),
+            Self::Damp => {
+                let sbs = dot(s, bs);
+                if sbs <= 0. || !sbs.is_finite() {
+                    return None;
+                }
+                if sy >= 0.2 * sbs {
+                    return Some(y);
+                }
+                let theta = 0.8 * sbs / (sbs - sy);
+                Some(
+                    y.iter()
+                        .zip(bs)
+                        .map(|(y, bs)| theta * y + (1. - theta) * bs)
+                        .collect(),
+                )
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Update {
+    Bfgs,
+    Dfp,
+    Sr1,
+}
+
+impl Update {
+    /// Updates the inverse Hessian approximation `h` with the pair `s`, `y`.
+    fn apply(self, h: &mut [f64], s: &[f64], y: &[f64]) {
+        let n = s.len();
+        let hy = mat_vec(h, y);
+        let sy = dot(s, y);
+        let yhy = dot(y, &hy);
+        // h += a u u^T + b (u v^T + v u^T) + c v v^T
+        let mut rank2 = |u: &[f64], v: &[f64], a: f64, b: f64, c: f64| {
+            for i in 0..n {
+                for j in 0..n {
+                    h[i * n + j] +=
+                        a * u[i] * u[j] + b * (u[i] * v[j] + v[i] * u[j]) + c * v[i] * v[j];
+                }
+            }
+        };
+        match self {
+            Self::Bfgs => {
+                let rho = 1. / sy;
+                rank2(s, &hy, rho * rho * yhy + rho, -rho, 0.);
+            }
+            Self::Dfp => rank2(s, &hy, 1. / sy, 0., -1. / yhy),
+            Self::Sr1 => {
+                let r = s.iter().zip(&hy).map(|(s, hy)| s - hy).collect::<Vec<_>>();
+                let ry = dot(&r, y);
+                // the usual safeguard, the update is skipped when its denominator is small
+                if ry.abs() > 1e-8 * norm(&r) * norm(y) {
+                    rank2(&r, &r, 1. / ry, 0., 0.);
+                }
+            }
+        }
+    }
+}
+
+/// Quasi-Newton iteration with a dense inverse Hessian approximation.
+fn dense(
+    objective: &dyn Objective,
+    x0: &[f64],
+    update: Update,
+    line_step: &LineStep,
+    curvature: Option<CurvatureGuard>,
+    convergence: Convergence,
+) -> Result<OptimizeResult, OptimizeError> {
+    let mut run = Run::start(objective, x0, convergence)?;
+    let n = x0.len();
+    let identity = (0..n * n)
+        .map(|ij| if ij % (n + 1) == 0 { 1. } else { 0. })
+        .collect::<Vec<_>>();
+
+    // `None` until the first update scales the identity
+    let mut h: Option<Vec<f64>> = None;
+    loop {
+        if let Some(termination) = run.termination() {
+            return Ok(run.finish(termination));
+        }
+        let (x0, g0) = (run.x().to_vec(), run.grad().to_vec());
+        let mut d = match &h {
+            Some(h) => mat_vec(h, &g0).iter().map(|hg| -hg).collect(),
+            None => g0.iter().map(|g| -g).collect::<Vec<_>>(),
+        };
+        if dot(&d, &g0) >= 0. {
+            d = g0.iter().map(|g| -g).collect();
+            h = None;
+        }
+        run.line_step(objective, line_step, &d)?;
+
+        let s = run
+            .x()
+            .iter()
+            .zip(&x0)
+            .map(|(a, b)| a - b)
+            .collect::<Vec<_>>();
+        let y = run.grad().iter().zip(&g0).map(|(a, b)| a - b).collect();
+        // `B d = -g` for the approximation the direction came from
+        let alpha = dot(&s, &d) / dot(&d, &d);
+        let bs = g0.iter().map(|g| -alpha * g).collect::<Vec<_>>();
+        let y = match curvature {
+            Some(guard) => match guard.pair(&s, y, &bs) {
+                Some(y) => y,
+                None => continue,
+            },
+            None => y,
+        };
+
+        let h = h.get_or_insert_with(|| {
+            let gamma = dot(&s, &y) / dot(&y, &y);
+            let gamma = if gamma.is_finite() && gamma > 0. {
+                gamma
+            } else {
+                1.
+            };
+            identity.iter().map(|i| gamma * i).collect()
+        });
+        update.apply(h, &s, &y);
+    }
+}
+
+macro_rules! impl_dense {
+    ($t:ident, $update:expr, $curvature:ident) => {
+        impl Default for $t {
+            fn default() -> Self {
+                Self::new()
+            }
+        }
+
+        impl $t {
+            /// Strong Wolfe line search.
+            pub fn new() -> Self {
+                Self {
+                    line_step: LineStep::StrongWolfe(StrongWolfe::default()),
+                    curvature: CurvatureGuard::default(),
+                    convergence: Convergence::default(),
+                }
+            }
+
+            pub fn with_line_step(mut self, line_step: impl Into<LineStep>) -> Self {
+                self.line_step = line_step.into();
+                self
+            }
+
+            pub fn with_curvature_guard(mut self, curvature: CurvatureGuard) -> Self {
+                self.curvature = curvature;
+                self
+            }
+
+            pub fn with_convergence(mut self, convergence: Convergence) -> Self {
+                self.convergence = convergence;
+                self
+            }
+        }
+
+        impl Optimizer for $t {
+            fn minimize(
+                &self,
+                objective: &dyn Objective,
+                x0: &[f64],
+            ) -> Result<OptimizeResult, OptimizeError> {
+                dense(
+                    objective,
+                    x0,
+                    $update,
+                    &self.line_step,
+                    $curvature(self.curvature),
+                    self.convergence,
+                )
+            }
+        }
+    };
+}
+
+/// Broyden-Fletcher-Goldfarb-Shanno, updates a dense inverse Hessian approximation
+/// scaled by `s . y / y . y` before the first update.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bfgs {
+    line_step: LineStep,
+    curvature: CurvatureGuard,
+    convergence: Convergence,
+}
+
+/// Davidon-Fletcher-Powell, the dual of BFGS, with the same dense inverse Hessian.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Dfp {
+    line_step: LineStep,
+    curvature: CurvatureGuard,
+    convergence: Convergence,
+}
+
+/// Symmetric rank one update of a dense inverse Hessian approximation.
+///
+/// The approximation may become indefinite, the direction is reset to steepest descent
+/// whenever it is not a descent direction. The curvature guard only applies in `Damp`
+/// mode, updates are skipped when the rank one denominator is small.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sr1 {
+    line_step: LineStep,
+    curvature: CurvatureGuard,
+    convergence: Convergence,
+}
+
+fn guard(curvature: CurvatureGuard) -> Option<CurvatureGuard> {
+    Some(curvature)
+}
+
+fn damp_only(curvature: CurvatureGuard) -> Option<CurvatureGuard> {
+    (curvature == CurvatureGuard::Damp).then_some(curvature)
+}
+
+impl_dense!(Bfgs, Update::Bfgs, guard);
+impl_dense!(Dfp, Update::Dfp, guard);
+impl_dense!(Sr1, Update::Sr1, damp_only);
+
+/// Limited-memory BFGS, applies the inverse Hessian approximation of the last `m` pairs
+/// `s`, `y` with the two-loop recursion in `O(m n)`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Lbfgs {
+    m: usize,
+    line_step: LineStep,
+    curvature: CurvatureGuard,
+    convergence: Convergence,
+}
+
+impl Default for Lbfgs {
+    fn default() -> Self {
+        Self::new(10)
+    }
+}
+
+impl Lbfgs {
+    /// History of `m` pairs and a strong Wolfe line search.
+    pub fn new(m: usize) -> Self {
+        Self {
+            m,
+            line_step: LineStep::StrongWolfe(StrongWolfe::default()),
+            curvature: CurvatureGuard::default(),
+            convergence: Convergence::default(),
+        }
+    }
+
+    pub fn with_history(mut self, m: usize) -> Self {
+        self.m = m;
+        self
+    }
+
+    pub fn with_line_step(mut self, line_step: impl Into<LineStep>) -> Self {
+        self.line_step = line_step.into();
+        self
+    }
+
+    pub fn with_curvature_guard(mut self, curvature: CurvatureGuard) -> Self {
+        self.curvature = curvature;
+        self
+    }
+
+    pub fn with_convergence(mut self, convergence: Convergence) -> Self {
+        self.convergence = convergence;
+        self
+    }
+}
+
+/// `-H g` for the L-BFGS approximation `H` of `history`, oldest pair first.
+fn two_loop(history: &VecDeque<(Vec<f64>, Vec<f64>, f64)>, g: &[f64]) -> Vec<f64> {
+    let mut q = g.to_vec();
+    let mut a = vec![0.; history.len()];
+    for ((s, y, rho), a) in history.iter().zip(&mut a).rev() {
+        *a = rho * dot(s, &q);
+        q.iter_mut().zip(y).for_each(|(q, y)| *q -= *a * y);
+    }
+    let gamma = history.back().map_or(1., |(s, y, _)| dot(s, y) / dot(y, y));
+    q.iter_mut().for_each(|q| *q *= gamma);
+    for ((s, y, rho), a) in history.iter().zip(&a) {
+        let b = rho * dot(y, &q);
+        q.iter_mut().zip(s).for_each(|(q, s)| *q += (a - b) * s);
+    }
+    q.iter_mut().for_each(|q| *q = -*q);
+    q
+}
+
+impl Optimizer for Lbfgs {
+    fn minimize(
+        &self,
+        objective: &dyn Objective,
+        x0: &[f64],
+    ) -> Result<OptimizeResult, OptimizeError> {
+        if self.m == 0 {
+            return Err(OptimizeError::InvalidParameter {
+                name: "m",
+                value: 0.,
+            });
+        }
+        let mut run = Run::start(objective, x0, self.convergence)?;
+
+        let mut history = VecDeque::with_capacity(self.m);
+        loop {
+            if let Some(termination) = run.termination() {
+                return Ok(run.finish(termination));
+            }
+            let (x0, g0) = (run.x().to_vec(), run.grad().to_vec());
+            let mut d = two_loop(&history, &g0);
+            if dot(&d, &g0) >= 0. {
+                d = g0.iter().map(|g| -g).collect();
+                history.clear();
+            }
+            run.line_step(objective, &self.line_step, &d)?;
+
+            let s = run
+                .x()
+                .iter()
+                .zip(&x0)
+                .map(|(a, b)| a - b)
+                .collect::<Vec<_>>();
+            let y = run.grad().iter().zip(&g0).map(|(a, b)| a - b).collect();
+            let alpha = dot(&s, &d) / dot(&d, &d);
+            let bs = g0.iter().map(|g| -alpha * g).collect::<Vec<_>>();
+            if let Some(y) = self.curvature.pair(&s, y, &bs) {
+                if history.len() == self.m {
+                    history.pop_front();
+                }
+                let rho = 1. / dot(&s, &y);
+                history.push_back((s, y, rho));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::bracketing::{LineMinimizer, Tolerance};
+    use crate::objective::FnObjective;
+    use crate::optimizer::{Beta, ConjugateGradient, Termination};
+    use crate::test_math_funcs::rosenbrock_objective;
+
+    fn optimizers() -> Vec<(&'static str, Box<dyn Optimizer>)> {
+        vec![
+            ("bfgs", Box::new(Bfgs::new())),
+            ("dfp", Box::new(Dfp::new())),
+            ("sr1", Box::new(Sr1::new())),
+            ("lbfgs", Box::new(Lbfgs::new(5))),
+            (
+                "damped bfgs",
+                Box::new(Bfgs::new().with_curvature_guard(CurvatureGuard::Damp)),
+            ),
+            (
+                "damped lbfgs",
+                Box::new(Lbfgs::new(5).with_curvature_guard(CurvatureGuard::Damp)),
+            ),
+        ]
+    }
+
+    /// `sum_i (1 - x_2i)^2 + 100 (x_2i+1 - x_2i^2)^2`
+    fn extended_rosenbrock(n: usize) -> FnObjective {
+        FnObjective::new(n, |x| {
+            x.chunks(2)
+                .map(|p| (1. - p[0]).powi(2) + 100. * (p[1] - p[0] * p[0]).powi(2))
+                .sum()
+        })
+        .with_gradient(|x, g| {
+            for (p, g) in x.chunks(2).zip(g.chunks_mut(2)) {
+                let t = p[1] - p[0] * p[0];
+                g[0] = -2. * (1. - p[0]) - 400. * p[0] * t;
+                g[1] = 200. * t;
+            }
+        })
+    }
+
+    #[test]
+    fn test_against_conjugate_gradient() {
+        // the conjugate gradient path of `examples/gradient_descent_2.rs`
+        let objective = rosenbrock_objective(1., 100.);
+        let cg = ConjugateGradient::new(Beta::PolakRibierePlus)
+            .with_line_step(
+                LineMinimizer::new()
+                    .with_step(1e-8)
+                    .with_tolerance(Tolerance::Relative(1e-6)),
+            )
+            .minimize(&objective, &[-1.4, 1.])
+            .unwrap();
+        let cg_evaluations = objective.evaluations();
+        assert!(cg.converged());
+
+        // Wolfe steps instead of exact line minimizations: within a small multiple of
+        // the conjugate gradient iterations, at a fraction of its evaluations
+        let mut iterations = HashMap::new();
+        for (name, optimizer) in optimizers() {
+            objective.reset_evaluations();
+            let res = optimizer.minimize(&objective, &[-1.4, 1.]).unwrap();
+            assert_eq!(res.termination, Termination::Gradient, "{name}");
+            assert!(res.x.iter().all(|x| (x - 1.).abs() < 1e-5), "{name}");
+            assert!(
+                res.iterations <= 3 * cg.iterations,
+                "{name} {} {}",
+                res.iterations,
+                cg.iterations
+            );
+            assert!(
+                objective.evaluations().value < cg_evaluations.value,
+                "{name} {:?} {cg_evaluations:?}",
+                objective.evaluations()
+            );
+            iterations.insert(name, res.iterations);
+        }
+        // DFP corrects a poor Hessian approximation much slower than BFGS
+        assert!(iterations["dfp"] > iterations["bfgs"], "{iterations:?}");
+    }
+
+    #[test]
+    fn test_quadratic_terminates() {
+        // exact line searches on a quadratic reach the minimum in at most n steps
+        let objective = FnObjective::new(3, |x| {
+            x[0] * x[0] + 2. * x[1] * x[1] + 5. * x[2] * x[2] + x[0] * x[1]
+        })
+        .with_gradient(|x, g| g.copy_from_slice(&[2. * x[0] + x[1], 4. * x[1] + x[0], 10. * x[2]]));
+        let exact = LineMinimizer::new()
+            .with_step(1e-8)
+            .with_tolerance(Tolerance::Relative(1e-12));
+        let convergence = Convergence {
+            gradient_tol: 1e-8,
+            ..Default::default()
+        };
+        for res in [
+            Bfgs::new()
+                .with_line_step(exact)
+                .with_convergence(convergence)
+                .minimize(&objective, &[1., 1., 1.]),
+            Dfp::new()
+                .with_line_step(exact)
+                .with_convergence(convergence)
+                .minimize(&objective, &[1., 1., 1.]),
+            Lbfgs::new(3)
+                .with_line_step(exact)
+                .with_convergence(convergence)
+                .minimize(&objective, &[1., 1., 1.]),
+        ] {
+            let res = res.unwrap();
+            assert!(res.iterations <= 4, "{}", res.iterations);
+        }
+    }
+
+    #[test]
+    fn test_curvature_guard() {
+        let s = [1., 0.];
+        let bs = [2., 0.];
+        // negative curvature is skipped or damped to s . r = 0.2 s . B s
+        assert_eq!(CurvatureGuard::Skip.pair(&s, vec![-1., 0.], &bs), None);
+        let r = CurvatureGuard::Damp.pair(&s, vec![-1., 0.], &bs).unwrap();
+        assert!((dot(&s, &r) - 0.4).abs() < 1e-12);
+        assert_eq!(
+            CurvatureGuard::Damp.pair(&s, vec![1., 3.], &bs),
+            Some(vec![1., 3.])
+        );
+    }
+
+    #[test]
+    fn test_lbfgs_large_problem() {
+        let n = 100_000;
+        let objective = extended_rosenbrock(n);
+        let x0 = (0..n)
+            .map(|i| if i % 2 == 0 { -1.2 } else { 1. })
+            .collect::<Vec<_>>();
+        let res = Lbfgs::new(5)
+            .with_convergence(Convergence {
+                gradient_tol: 1e-5,
+                keep_trajectory: false,
+                ..Default::default()
+            })
+            .minimize(&objective, &x0)
+            .unwrap();
+        assert_eq!(res.termination, Termination::Gradient);
+        assert!(res.iterations < 100, "{}", res.iterations);
+        assert!(res.x.iter().all(|x| (x - 1.).abs() < 1e-4));
+        assert_eq!(res.trajectory, vec![res.x.clone()]);
+        assert_eq!(res.values, vec![res.fx]);
+    }
+
+    #[test]
+    fn test_invalid_history() {
+        let err = Lbfgs::new(0)
+            .minimize(&rosenbrock_objective(1., 100.), &[-1.4, 1.])
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            OptimizeError::InvalidParameter { name: "m", .. }
+        ));
+    }
+}
@@ -0,0 +1,527 @@
+use std::rc::Rc;
+
+use crate::autograd::compute_graph::{node::Node, tape::Tape};
+use crate::autograd::dual::Dual;
+use crate::bracketing::{
+    check_bracket, check_expansion, BracketError, Recorder, SearchResult, Tolerance,
+};
+use crate::objective::UnivariateFn;
+
+/// Whether a root lies between points with values `fa` and `fb`.
+fn sign_change(fa: f64, fb: f64) -> bool {
+    fa == 0. || fb == 0. || (fa < 0.) != (fb < 0.)
+}
+
+/// A point `x` and `f(x)`.
+type Point = (f64, f64);
+
+/// The point with the smaller `|f|`.
+fn closest((a, fa): Point, (b, fb): Point) -> Point {
+    if fa.abs() <= fb.abs() {
+        (a, fa)
+    } else {
+        (b, fb)
+    }
+}
+
+/// Expands `(x0, x0 + s)` until `f` changes sign over it, moving the end with the
+/// smaller `|f|` outwards by `k` times the current width.
+///
+/// `x` of the result is the bracket end with the smaller `|f|`.
+pub fn bracket_sign_change(
+    f: &dyn UnivariateFn,
+    x0: f64,
+    s: f64,
+    k: f64,
+    iter_limit: usize,
+) -> Result<SearchResult, BracketError> {
+    check_expansion(s, k)?;
+
+    let mut f = Recorder::new(f);
+    let (mut a, mut fa) = (x0, f.eval(x0)?);
+    let (mut b, mut fb) = (x0 + s, f.eval(x0 + s)?);
+
+    for _ in 0..iter_limit {
+        if sign_change(fa, fb) {
+            break;
+        }
+        let step = k * (b - a);
+        if fa.abs() < fb.abs() {
+            a -= step;
+            if !a.is_finite() {
+                return Err(BracketError::NoSignChange { fa, fb });
+            }
+            fa = f.eval(a)?;
+        } else {
+            b += step;
+            if !b.is_finite() {
+                return Err(BracketError::NoSignChange { fa, fb });
+            }
+            fb = f.eval(b)?;
+        }
+    }
+
+    let (x, fx) = closest((a, fa), (b, fb));
+    if sign_change(fa, fb) {
+        Ok(f.finish(x, fx, (a, b)))
+    } else {
+        Err(BracketError::IterationLimit {
+            iterations: iter_limit,
+            x,
+            fx,
+        })
+    }
+}
+
+/// Evaluates both ends of `bracket` and checks that `f` changes sign over it.
+fn bracket_ends(
+    f: &mut Recorder<f64>,
+    bracket: (f64, f64),
+) -> Result<(Point, Point), BracketError> {
+    let (a, b) = check_bracket(bracket)?;
+    let (fa, fb) = (f.eval(a)?, f.eval(b)?);
+    if sign_change(fa, fb) {
+        Ok(((a, fa), (b, fb)))
+    } else {
+        Err(BracketError::NoSignChange { fa, fb })
+    }
+}
+
+/// Halves `bracket`, over which `f` has to change sign, until it is within `tol`.
+///
+/// `x` of the result is the bracket end with the smaller `|f|`.
+pub fn bisection(
+    f: &dyn UnivariateFn,
+    bracket: (f64, f64),
+    tol: Tolerance,
+) -> Result<SearchResult, BracketError> {
+    tol.check()?;
+    let mut f = Recorder::new(f);
+    let ((mut a, mut fa), (mut b, mut fb)) = bracket_ends(&mut f, bracket)?;
+
+    while fa != 0. && fb != 0. {
+        let x = a + (b - a) / 2.;
+        if b - a <= tol.width(x) || x == a || x == b {
+            break;
+        }
+        let fx = f.eval(x)?;
+        if sign_change(fa, fx) {
+            (b, fb) = (x, fx);
+        } else {
+            (a, fa) = (x, fx);
+        }
+    }
+
+    let (x, fx) = closest((a, fa), (b, fb));
+    Ok(f.finish(x, fx, (a, b)))
+}
+
+/// Shared iteration of `regula_falsi` and `illinois`.
+fn false_position(
+    f: &dyn UnivariateFn,
+    bracket: (f64, f64),
+    tol: Tolerance,
+    max_iter: usize,
+    illinois: bool,
+) -> Result<SearchResult, BracketError> {
+    tol.check()?;
+    let mut f = Recorder::new(f);
+    let ((mut a, mut fa), (mut b, mut fb)) = bracket_ends(&mut f, bracket)?;
+    if fa == 0. || fb == 0. {
+        let (x, fx) = closest((a, fa), (b, fb));
+        return Ok(f.finish(x, fx, (a, b)));
+    }
+
+    // end replaced by the previous step, -1 for `a` and 1 for `b`
+    let mut replaced = 0;
+    let mut last = closest((a, fa), (b, fb));
+    for i in 0..max_iter {
+        let x = (a * fb - b * fa) / (fb - fa);
+        let fx = f.eval(x)?;
+        let converged =
+            fx == 0. || b - a <= tol.width(x) || (i > 0 && (x - last.0).abs() <= tol.width(x));
+
+        if sign_change(fa, fx) {
+            (b, fb) = (x, fx);
+            if illinois && replaced == 1 {
+                fa /= 2.;
+            }
+            replaced = 1;
+        } else {
+            (a, fa) = (x, fx);
+            if illinois && replaced == -1 {
+                fb /= 2.;
+            }
+            replaced = -1;
+        }
+
+        last = (x, fx);
+        if converged {
+            return Ok(f.finish(x, fx, (a, b)));
+        }
+    }
+
+    Err(BracketError::NotConverged {
+        iterations: max_iter,
+        x: last.0,
+        fx: last.1,
+    })
+}
+
+/// Regula falsi, the root of the secant through the bracket ends replaces the end
+/// with the same sign.
+///
+/// Stops once a step or the bracket is within `tol`. On convex or concave functions
+/// one end never moves and convergence is only linear, see `illinois`.
+pub fn regula_falsi(
+    f: &dyn UnivariateFn,
+    bracket: (f64, f64),
+    tol: Tolerance,
+    max_iter: usize,
+) -> Result<SearchResult, BracketError> {
+    false_position(f, bracket, tol, max_iter, false)
+}
+
+/// `regula_falsi` with the Illinois modification, halving the value kept at an end
+/// that survives two steps in a row, which restores superlinear convergence.
+pub fn illinois(
+    f: &dyn UnivariateFn,
+    bracket: (f64, f64),
+    tol: Tolerance,
+    max_iter: usize,
+) -> Result<SearchResult, BracketError> {
+    false_position(f, bracket, tol, max_iter, true)
+}
+
+/// Secant method from the two starting points `x0` and `x1`, no sign change needed.
+///
+/// Stops once a step is within `tol`. The bracket of the result holds the last two
+/// iterates and need not contain the root.
+pub fn secant(
+    f: &dyn UnivariateFn,
+    (x0, x1): (f64, f64),
+    tol: Tolerance,
+    max_iter: usize,
+) -> Result<SearchResult, BracketError> {
+    check_bracket((x0, x1))?;
+    tol.check()?;
+    let mut f = Recorder::new(f);
+    let (mut x0, mut f0) = (x0, f.eval(x0)?);
+    let (mut x1, mut f1) = (x1, f.eval(x1)?);
+
+    for _ in 0..max_iter {
+        if f1 == 0. {
+            return Ok(f.finish(x1, f1, (x0, x1)));
+        }
+        if f1 == f0 {
+            return Err(BracketError::ZeroSlope { x: x1, fx: f1 });
+        }
+        let x2 = x1 - f1 * (x1 - x0) / (f1 - f0);
+        let f2 = f.eval(x2)?;
+        (x0, f0, x1, f1) = (x1, f1, x2, f2);
+        if (x1 - x0).abs() <= tol.width(x1) {
+            return Ok(f.finish(x1, f1, (x0, x1)));
+        }
+    }
+
+    Err(BracketError::NotConverged {
+        iterations: max_iter,
+        x: x1,
+        fx: f1,
+    })
+}
+
+/// Newton's method from `x0`, with the derivative of `f`.
+///
+/// Stops once a step is within `tol`, the bracket of the result holds the last two
+/// iterates. `UnivariateFn::derivative` falls back to central differences, see
+/// `newton_graph` and `newton_dual` for exact ones.
+pub fn newton(
+    f: &dyn UnivariateFn,
+    x0: f64,
+    tol: Tolerance,
+    max_iter: usize,
+) -> Result<SearchResult, BracketError> {
+    tol.check()?;
+    let mut f = Recorder::new(f);
+    let mut x = x0;
+    let (mut fx, mut dfx) = f.eval_with_derivative(x)?;
+
+    for _ in 0..max_iter {
+        if fx == 0. {
+            return Ok(f.finish(x, fx, (x, x)));
+        }
+        if dfx == 0. {
+            return Err(BracketError::ZeroSlope { x, fx });
+        }
+        if !dfx.is_finite() {
+            return Err(BracketError::NonFinite { x, fx: dfx });
+        }
+        let prev = x;
+        x -= fx / dfx;
+        (fx, dfx) = f.eval_with_derivative(x)?;
+        if (x - prev).abs() <= tol.width(x) {
+            return Ok(f.finish(x, fx, (prev, x)));
+        }
+    }
+
+    Err(BracketError::NotConverged {
+        iterations: max_iter,
+        x,
+        fx,
+    })
+}
+
+/// Compute graph function, differentiated by `Node::auto_grad`.
+struct GraphFn<'a>(&'a dyn Fn(Node) -> Node);
+
+impl UnivariateFn for GraphFn<'_> {
+    fn value(&self, x: f64) -> f64 {
+        (self.0)(Tape::new().var(x)).value()
+    }
+
+    fn derivative(&self, x: f64) -> f64 {
+        self.value_and_derivative(x).1
+    }
+
+    fn value_and_derivative(&self, x: f64) -> (f64, f64) {
+        let y = (self.0)(Tape::new().var(x));
+        let value = y.value();
+        let grad = Node::auto_grad(Rc::new(y))
+            .first()
+            .map_or(0., |pair| pair.grad());
+        (value, grad)
+    }
+}
+
+/// Dual number function, differentiated in forward mode.
+struct DualFn<'a>(&'a dyn Fn(Dual) -> Dual);
+
+impl UnivariateFn for DualFn<'_> {
+    fn value(&self, x: f64) -> f64 {
+        (self.0)(Dual::constant(x)).value()
+    }
+
+    fn derivative(&self, x: f64) -> f64 {
+        (self.0)(Dual::var(x)).derivative()
+    }
+
+    fn value_and_derivative(&self, x: f64) -> (f64, f64) {
+        let y = (self.0)(Dual::var(x));
+        (y.value(), y.derivative())
+    }
+}
+
+/// `newton` with the derivative of a compute graph function from `Node::auto_grad`.
+pub fn newton_graph(
+    f: &dyn Fn(Node) -> Node,
+    x0: f64,
+    tol: Tolerance,
+    max_iter: usize,
+) -> Result<SearchResult, BracketError> {
+    newton(&GraphFn(f), x0, tol, max_iter)
+}
+
+/// `newton` with the derivative from a dual number evaluation of `f`.
+pub fn newton_dual(
+    f: &dyn Fn(Dual) -> Dual,
+    x0: f64,
+    tol: Tolerance,
+    max_iter: usize,
+) -> Result<SearchResult, BracketError> {
+    newton(&DualFn(f), x0, tol, max_iter)
+}
+
+/// Brent-Dekker method, inverse quadratic interpolation and secant steps safeguarded
+/// by bisection, over a `bracket` where `f` changes sign.
+///
+/// Stops once the bracket around the best point is within `tol`.
+pub fn brent(
+    f: &dyn UnivariateFn,
+    bracket: (f64, f64),
+    tol: Tolerance,
+    max_iter: usize,
+) -> Result<SearchResult, BracketError> {
+    tol.check()?;
+    let mut f = Recorder::new(f);
+    let ((mut a, mut fa), (mut b, mut fb)) = bracket_ends(&mut f, bracket)?;
+    // b is the best point, c the other end of the bracket and a the previous b
+    let (mut c, mut fc) = (b, fb);
+    // d is the last step and e the one before it
+    let (mut d, mut e) = (b - a, b - a);
+
+    for _ in 0..max_iter {
+        if !sign_change(fb, fc) {
+            (c, fc) = (a, fa);
+            (d, e) = (b - a, b - a);
+        }
+        if fc.abs() < fb.abs() {
+            (a, fa, b, fb, c, fc) = (b, fb, c, fc, b, fb);
+        }
+
+        let tol1 = 2. * f64::EPSILON * b.abs() + 0.5 * tol.width(b);
+        let m = 0.5 * (c - b);
+        if m.abs() <= tol1 || fb == 0. {
+            return Ok(f.finish(b, fb, (b, c)));
+        }
+
+        let mut bisect = true;
+        if e.abs() >= tol1 && fa.abs() > fb.abs() {
+            let s = fb / fa;
+            let (mut p, mut q) = if a == c {
+                // secant
+                (2. * m * s, 1. - s)
+            } else {
+                // inverse quadratic interpolation
+                let (q, r) = (fa / fc, fb / fc);
+                (
+                    s * (2. * m * q * (q - r) - (b - a) * (r - 1.)),
+                    (q - 1.) * (r - 1.) * (s - 1.),
+                )
+            };
+            if p > 0. {
+                q = -q;
+            } else {
+                p = -p;
+            }
+
+            // accept if it stays inside and moves less than half the step before last
+            if 2. * p < (3. * m * q - (tol1 * q).abs()).min((e * q).abs()) {
+                (e, d) = (d, p / q);
+                bisect = false;
+            }
+        }
+        if bisect {
+            (d, e) = (m, m);
+        }
+
+        (a, fa) = (b, fb);
+        b += if d.abs() > tol1 { d } else { tol1.copysign(m) };
+        fb = f.eval(b)?;
+    }
+
+    Err(BracketError::NotConverged {
+        iterations: max_iter,
+        x: b,
+        fx: fb,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // the classic example of Wallis, with a single real root
+    fn wallis(x: f64) -> f64 {
+        x * x * x - 2. * x - 5.
+    }
+
+    const WALLIS_ROOT: f64 = 2.0945514815423265;
+
+    #[test]
+    fn test_bracket_sign_change() {
+        let res = bracket_sign_change(&wallis, 0., 0.1, 2., 50).unwrap();
+        let (a, b) = res.bracket;
+        assert!(a < WALLIS_ROOT && WALLIS_ROOT < b);
+        assert!(wallis(a) < 0. && wallis(b) > 0.);
+        assert_eq!(res.fx, wallis(res.x));
+        assert_eq!(res.evaluations, res.history.len());
+
+        // expands to the left towards the smaller values too
+        let res = bracket_sign_change(&|x: f64| x + 100., 10., 1., 2., 50).unwrap();
+        assert!(res.bracket.0 <= -100. && -100. <= res.bracket.1);
+
+        let res = bracket_sign_change(&|x: f64| x * x + 1., 0., 1., 2., 10);
+        assert!(matches!(
+            res,
+            Err(BracketError::IterationLimit { iterations: 10, .. })
+        ));
+        assert_eq!(
+            bracket_sign_change(&wallis, 0., 0.1, 1., 10),
+            Err(BracketError::InvalidExpansion(1.))
+        );
+    }
+
+    #[test]
+    fn test_bracketed_roots() {
+        let tol = Tolerance::Absolute(1e-12);
+        let bisected = bisection(&wallis, (2., 3.), tol).unwrap();
+        let falsi = regula_falsi(&wallis, (2., 3.), tol, 200).unwrap();
+        let illinois = illinois(&wallis, (2., 3.), tol, 100).unwrap();
+        let brent = brent(&wallis, (2., 3.), tol, 100).unwrap();
+
+        for res in [&bisected, &falsi, &illinois, &brent] {
+            assert!((res.x - WALLIS_ROOT).abs() < 1e-11);
+            assert_eq!(res.fx, wallis(res.x));
+            assert_eq!(res.evaluations, res.history.len());
+            let (a, b) = res.bracket;
+            assert!(a <= WALLIS_ROOT && WALLIS_ROOT <= b);
+        }
+        assert!(bisected.bracket.1 - bisected.bracket.0 <= 1e-12);
+        assert!(brent.evaluations < illinois.evaluations);
+        assert!(illinois.evaluations < falsi.evaluations);
+        assert!(falsi.evaluations < bisected.evaluations);
+    }
+
+    #[test]
+    fn test_illinois_on_convex_function() {
+        // regula falsi keeps the left end and creeps in from the right
+        let f = |x: f64| x.powi(10) - 1.;
+        let tol = Tolerance::Absolute(1e-12);
+        assert!(matches!(
+            regula_falsi(&f, (0., 1.3), tol, 50),
+            Err(BracketError::NotConverged { iterations: 50, .. })
+        ));
+        let res = illinois(&f, (0., 1.3), tol, 50).unwrap();
+        assert!((res.x - 1.).abs() < 1e-12);
+        let res = brent(&f, (0., 1.3), tol, 50).unwrap();
+        assert!((res.x - 1.).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_open_methods() {
+        let tol = Tolerance::Relative(1e-14);
+        let res = secant(&wallis, (1., 3.), tol, 50).unwrap();
+        assert!((res.x - WALLIS_ROOT).abs() < 1e-14);
+
+        let res = newton_dual(&|x| x.powi(3) - 2. * x - 5., 1., tol, 50).unwrap();
+        assert!((res.x - WALLIS_ROOT).abs() < 1e-14);
+        let graph = newton_graph(&|x| x.powi(3) - 2. * &x - 5., 1., tol, 50).unwrap();
+        assert_eq!(graph.history, res.history);
+
+        let res = newton(&wallis, 1., tol, 50).unwrap();
+        assert!((res.x - WALLIS_ROOT).abs() < 1e-14);
+    }
+
+    #[test]
+    fn test_root_errors() {
+        let tol = Tolerance::Absolute(1e-10);
+        assert_eq!(
+            bisection(&wallis, (3., 4.), tol),
+            Err(BracketError::NoSignChange {
+                fa: wallis(3.),
+                fb: wallis(4.)
+            })
+        );
+        assert_eq!(
+            brent(&wallis, (2., 2.), tol, 10),
+            Err(BracketError::InvalidBracket(2., 2.))
+        );
+        assert_eq!(
+            newton_dual(&|x| x * x + 1., 0., tol, 10),
+            Err(BracketError::ZeroSlope { x: 0., fx: 1. })
+        );
+        assert_eq!(
+            secant(&|_| 1., (0., 1.), tol, 10),
+            Err(BracketError::ZeroSlope { x: 1., fx: 1. })
+        );
+        assert!(matches!(
+            newton(&|x: f64| x * x + 1., 0.3, tol, 10),
+            Err(BracketError::NotConverged { iterations: 10, .. })
+        ));
+        assert!(matches!(
+            newton(&|x: f64| x.ln(), 3., tol, 10),
+            Err(BracketError::NonFinite { .. })
+        ));
+    }
+}
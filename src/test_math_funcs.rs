@@ -1,3 +1,5 @@
+use crate::objective::FnObjective;
+
 pub fn rosenbrock(x: &[f64; 2], a: f64, b: f64) -> f64 {
     (a - x[0]).powi(2) + b * (x[1] - x[0].powi(2)).powi(2)
 }
@@ -7,3 +9,21 @@ pub fn rosenbrock_grad(x: &[f64; 2], a: f64, b: f64) -> [f64; 2] {
     let g_1 = 2. * b * (x[1] - x[0].powi(2));
     [g_0, g_1]
 }
+
+pub fn rosenbrock_hessian(x: &[f64; 2], _a: f64, b: f64) -> [[f64; 2]; 2] {
+    let h_00 = 12. * b * x[0].powi(2) - 4. * b * x[1] + 2.;
+    let h_01 = -4. * b * x[0];
+    let h_11 = 2. * b;
+    [[h_00, h_01], [h_01, h_11]]
+}
+
+pub fn rosenbrock_objective(a: f64, b: f64) -> FnObjective {
+    FnObjective::new(2, move |x| rosenbrock(&[x[0], x[1]], a, b))
+        .with_gradient(move |x, grad| {
+            grad.copy_from_slice(&rosenbrock_grad(&[x[0], x[1]], a, b));
+        })
+        .with_hessian(move |x, hess| {
+            let h = rosenbrock_hessian(&[x[0], x[1]], a, b);
+            hess.copy_from_slice(&[h[0][0], h[0][1], h[1][0], h[1][1]]);
+        })
+}